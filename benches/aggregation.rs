@@ -0,0 +1,120 @@
+//! Benchmarks the aggregation of the upstream search results by feeding synthetic engine outputs
+//! with partially overlapping urls into `aggregate`.
+
+use std::sync::Arc;
+
+use actix_web::web::Data;
+use crabbysearch::{
+    config::Config,
+    health::EngineHealth,
+    metrics::Metrics,
+    models::{
+        aggregation_models::SearchResult,
+        engine_models::{EngineError, EngineHandler, EngineInfo, SearchEngine},
+    },
+    results::{aggregator::aggregate, browser_profile::BrowserProfile},
+};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use error_stack::Result;
+use reqwest::Client;
+
+/// The names of the synthetic engines.
+const ENGINE_NAMES: [&str; 7] = [
+    "engine_a", "engine_b", "engine_c", "engine_d", "engine_e", "engine_f", "engine_g",
+];
+
+/// A search engine which returns a precomputed list of results without any network access.
+struct SyntheticEngine {
+    /// The results returned for every query.
+    results: Vec<(String, SearchResult)>,
+}
+
+impl SyntheticEngine {
+    /// Creates an engine returning `count` results, half of which are shared with every other
+    /// engine (in a different url form) and half of which are unique to this engine.
+    fn new(name: &'static str, count: usize) -> Self {
+        let results = (0..count)
+            .map(|index| {
+                let url = match index % 2 {
+                    0 => format!("https://www.example.com/shared/{index}/?utm_source={name}"),
+                    _ => format!("https://{name}.example.com/unique/{index}"),
+                };
+                let result = SearchResult::new(
+                    &format!("Result {index} about the rust programming language"),
+                    &url,
+                    &format!("A description of result {index} returned by {name} for the query"),
+                    &[name],
+                );
+                (url, result)
+            })
+            .collect();
+        Self { results }
+    }
+}
+
+#[async_trait::async_trait]
+impl SearchEngine for SyntheticEngine {
+    async fn results(
+        &self,
+        _query: &str,
+        _page: u32,
+        _profile: &BrowserProfile,
+        _client: &Client,
+        _safe_search: u8,
+    ) -> Result<Vec<(String, SearchResult)>, EngineError> {
+        Ok(self.results.clone())
+    }
+}
+
+/// Benchmarks `aggregate` with all synthetic engines for different numbers of results per engine.
+fn bench_aggregate(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let config = Data::new(Config::default());
+    let health = EngineHealth::build(&config);
+    let metrics = Metrics::new();
+
+    let mut group = c.benchmark_group("aggregate");
+    for results_per_engine in [10, 50, 200] {
+        let engines: Vec<EngineHandler> = ENGINE_NAMES
+            .iter()
+            .map(|name| {
+                let info = EngineInfo {
+                    display_name: name.to_string(),
+                    categories: vec!["general".to_string()],
+                    supports_paging: true,
+                    supports_safe_search: true,
+                };
+                EngineHandler::new(
+                    name,
+                    info,
+                    Arc::new(SyntheticEngine::new(name, results_per_engine)),
+                )
+            })
+            .collect();
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(results_per_engine),
+            &engines,
+            |b, engines| {
+                b.iter(|| {
+                    runtime
+                        .block_on(aggregate(
+                            "rust programming",
+                            0,
+                            0,
+                            config.clone(),
+                            &health,
+                            true,
+                            &metrics,
+                            engines,
+                        ))
+                        .unwrap()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_aggregate);
+criterion_main!(benches);
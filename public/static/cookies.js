@@ -16,7 +16,7 @@ function setClientSettingsOnPage(cookie) {
   document.querySelector('.select_all').checked = true
 
   engines.forEach((engine) => {
-    engine.checked = cookie[engine.parentNode.parentNode.innerText.trim()];
+    engine.checked = cookie[engine.dataset.engine] === true
     if (!engine.checked) {
       document.querySelector('.select_all').checked = false
     }
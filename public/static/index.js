@@ -32,3 +32,50 @@ searchBox.addEventListener('keyup', (e) => {
         searchWeb()
     }
 })
+
+/**
+ * Selects the datalist element which holds the search suggestions for the search box
+ * @type {HTMLDataListElement}
+ */
+const suggestionList = document.querySelector('#search-suggestions')
+
+/**
+ * Holds the timeout id of the pending suggestions request so that requests are only sent
+ * once the user pauses typing
+ * @type {number|undefined}
+ */
+let suggestionTimeout
+
+/**
+ * Fetches the search suggestions for the current value of the search box and shows them
+ * in the suggestions datalist
+ */
+async function fetchSuggestions() {
+    const query = searchBox.value.trim()
+    if (!query) {
+        suggestionList.replaceChildren()
+        return
+    }
+    try {
+        const response = await fetch(`/suggest?q=${encodeURIComponent(query)}`)
+        const [, suggestions] = await response.json()
+        suggestionList.replaceChildren(
+            ...suggestions.map((suggestion) => {
+                const option = document.createElement('option')
+                option.value = suggestion
+                return option
+            }),
+        )
+    } catch (error) {
+        console.error('Error fetching suggestions:', error)
+    }
+}
+
+/**
+ * Listens for the 'input' event on the search box and fetches the suggestions once the user
+ * stops typing for a short moment
+ */
+searchBox.addEventListener('input', () => {
+    clearTimeout(suggestionTimeout)
+    suggestionTimeout = setTimeout(fetchSuggestions, 200)
+})
@@ -6,7 +6,6 @@ function navigate_forward() {
     let url = new URL(window.location);
     let searchParams = url.searchParams;
 
-    let q = searchParams.get('q');
     let page = parseInt(searchParams.get('page'));
 
     if (isNaN(page)) {
@@ -15,7 +14,9 @@ function navigate_forward() {
         page++;
     }
 
-    window.location.href = `${url.origin}${url.pathname}?q=${encodeURIComponent(q)}&page=${page}`;
+    // keep the other search parameters (like the safe search level) while changing the page.
+    searchParams.set('page', page);
+    window.location.href = url.toString();
 }
 
 /**
@@ -26,7 +27,6 @@ function navigate_backward() {
     let url = new URL(window.location);
     let searchParams = url.searchParams;
 
-    let q = searchParams.get('q');
     let page = parseInt(searchParams.get('page'));
 
     if (isNaN(page)) {
@@ -35,5 +35,6 @@ function navigate_backward() {
         page--;
     }
 
-    window.location.href = `${url.origin}${url.pathname}?q=${encodeURIComponent(q)}&page=${page}`;
+    searchParams.set('page', page);
+    window.location.href = url.toString();
 }
@@ -42,7 +42,7 @@ function setClientSettings() {
   let cookie_dictionary = new Object()
 
   document.querySelectorAll('.engine').forEach((engine_checkbox) => {
-    cookie_dictionary[engine_checkbox.parentNode.parentNode.innerText.trim()] = engine_checkbox.checked
+    cookie_dictionary[engine_checkbox.dataset.engine] = engine_checkbox.checked
   })
 
   // Set the expiration date for the cookie to 1 year from the current date
@@ -26,6 +26,8 @@ impl From<&SearchResults> for Vec<u8> {
 pub struct Cache {
     /// The backend cache which stores data.
     cache: MokaCache<String, Vec<u8>>,
+    /// The backend cache which stores the search suggestions, with a shorter time to live.
+    suggestions: MokaCache<String, Vec<String>>,
 }
 
 impl Cache {
@@ -37,6 +39,9 @@ impl Cache {
             cache: MokaCache::builder()
                 .time_to_live(Duration::from_secs(config.cache_expiry_time))
                 .build(),
+            suggestions: MokaCache::builder()
+                .time_to_live(Duration::from_secs(config.suggestion_cache_expiry_time))
+                .build(),
         }
     }
 
@@ -53,4 +58,15 @@ impl Cache {
 
         self.cache.sync();
     }
+
+    /// Retrieve cached suggestions
+    pub fn cached_suggestions(&self, key: &str) -> Option<Vec<String>> {
+        self.suggestions.get(&key.to_string())
+    }
+
+    /// Cache suggestions
+    pub fn cache_suggestions(&self, key: &str, suggestions: &[String]) {
+        self.suggestions
+            .insert(key.to_string(), suggestions.to_vec());
+    }
 }
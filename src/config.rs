@@ -1,8 +1,19 @@
 //! Config module
 
-use figment::{providers::Serialized, Figment};
+use std::collections::{BTreeMap, HashMap};
+
+use actix_web::dev::ConnectionInfo;
+use figment::{
+    providers::{Env, Format, Serialized, Toml},
+    Figment,
+};
 use serde::{Deserialize, Serialize};
 
+use crate::handler::{file_path, FileType};
+
+/// The proxy setting which makes an engine connect directly, bypassing the global proxy.
+pub const DIRECT: &str = "direct";
+
 /// Struct holding config Options
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Config {
@@ -10,17 +21,66 @@ pub struct Config {
     pub port: u16,
     /// It stores the parsed ip address option on which the server should launch
     pub binding_ip: String,
+    /// It stores the public base url (e.g. `https://search.example.com`) under which the server
+    /// is reachable. The scheme and the host of the requests are used if it is missing.
+    pub public_url: Option<String>,
     /// Memory cache invalidation time
     pub cache_expiry_time: u64,
+    /// Memory cache invalidation time for the search suggestions
+    pub suggestion_cache_expiry_time: u64,
     /// It stores the option to whether enable or disable logs.
     pub logging: bool,
     /// It stores the option to whether enable or disable debug mode.
     pub debug: bool,
     /// It toggles whether to use adaptive HTTP windows
     pub adaptive_window: bool,
-    /// It stores all the engine names that were enabled by the user.
-    pub upstream_search_engines: crate::engines::Engines,
-    /// It stores the time (secs) which controls the server request timeout.
+    /// It stores the names of the engines which are selected by default, for users who have not
+    /// selected engines on the settings page.
+    pub upstream_search_engines: Vec<String>,
+    /// It stores the weight of each upstream engine when fusing the engine rankings. Engines
+    /// which are not listed have a weight of `1.0`. The weights have to be finite and at least
+    /// `0`.
+    pub engine_weights: HashMap<String, f32>,
+    /// It stores the declarative html engines defined in the config file, keyed by their engine
+    /// name.
+    pub html_engines: BTreeMap<String, HtmlEngineConfig>,
+    /// It stores the declarative json api engines defined in the config file, keyed by their
+    /// engine name.
+    pub json_engines: BTreeMap<String, JsonEngineConfig>,
+    /// It stores the instances of the searx meta search engine which are queried.
+    pub searx: SearxConfig,
+    /// It stores the instances of the librex meta search engine which are queried.
+    pub librex: InstancePoolConfig,
+    /// It stores the base urls (e.g. `https://www.bing.com`) which replace the default base urls
+    /// of the built-in engines, keyed by their engine name. The instances of searx and librex are
+    /// configured in their own sections.
+    pub engine_base_urls: BTreeMap<String, String>,
+    /// It stores the timeout, retry and backoff policy applied to the requests of every upstream
+    /// engine.
+    pub engine_policy: EnginePolicy,
+    /// It stores the policies which override the default engine policy for single engines, keyed
+    /// by their engine name. The settings missing in an override are taken from the default
+    /// engine policy.
+    pub engine_policies: BTreeMap<String, EnginePolicyOverride>,
+    /// It stores after how many failures the upstream engines are skipped and for how long.
+    pub circuit_breaker: CircuitBreakerConfig,
+    /// It stores the time (secs) over which the error rate and the latencies of the upstream
+    /// engines are computed for the health route and the status page.
+    pub health_window: u64,
+    /// It stores the time (millisecs) after which the results of the engines which answered so
+    /// far are returned, the engines which did not answer by then are reported as timed out. All
+    /// engines are awaited if it is missing.
+    pub aggregation_deadline: Option<u64>,
+    /// It stores the number of engines which have to answer with results before the results are
+    /// returned, the other engines are reported as timed out. All engines are awaited if it is
+    /// missing.
+    pub aggregation_quorum: Option<usize>,
+    /// It stores the option to whether await the engines which did not answer in time in the
+    /// background and merge their results into the cached results, so that the next page load
+    /// gets the complete results.
+    pub cache_late_results: bool,
+    /// It stores the time (secs) which controls the server request timeout. It caps the timeout
+    /// of every engine policy, as it is the timeout of the client shared by the engines.
     pub request_timeout: u8,
     /// Set the keep-alive time for client connections to the HTTP server
     //pub client_connection_keep_alive: u8,
@@ -28,6 +88,259 @@ pub struct Config {
     pub tcp_connection_keep_alive: u8,
     /// It stores the pool idle connection timeout in seconds.
     pub pool_idle_connection_timeout: u8,
+    /// It stores the url of the proxy through which the upstream engines are requested, e.g.
+    /// `socks5h://127.0.0.1:9050` for a local tor daemon (the `h` resolves the host names
+    /// through the proxy) or `http://proxy.example.com:3128`. The proxy of the environment (like
+    /// `HTTPS_PROXY`) is used if it is missing.
+    pub proxy: Option<String>,
+    /// It stores the proxy urls which override the global proxy for single engines, keyed by
+    /// their engine name. The `direct` setting connects the engine without a proxy.
+    pub engine_proxies: BTreeMap<String, String>,
+    /// It stores the option to whether only request the upstream engines over https, it can be
+    /// disabled to query engines (or mock engines in tests) served over plain http.
+    pub https_only: bool,
+}
+
+/// A named struct which describes the parts shared by all the declarative engines defined in the
+/// config file: how the engine is shown in the UI and how it is requested.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct DeclarativeEngineConfig {
+    /// It stores the name of the engine shown in the UI, the engine name is used if it is missing.
+    pub display_name: Option<String>,
+    /// It stores the categories of search results the engine provides.
+    #[serde(default = "default_categories")]
+    pub categories: Vec<String>,
+    /// It stores the url template of the search page. The `{query}` placeholder is replaced with
+    /// the encoded query, `{page}` with the page number (starting from 1), `{offset}` with the
+    /// index of the first result of the page and `{safe_search}` with the safe search level.
+    pub url: String,
+    /// It stores the number of results per page, which is used to compute the `{offset}`.
+    #[serde(default = "default_results_per_page")]
+    pub results_per_page: u32,
+    /// It stores the additional http headers sent to the engine.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// It stores the cookies sent to the engine.
+    #[serde(default)]
+    pub cookies: BTreeMap<String, String>,
+}
+
+/// A named struct which describes an upstream search engine serving html result pages, which is
+/// scraped with the css selectors of a `SearchResultParser`.
+///
+/// # Example
+///
+/// ```toml
+/// [html_engines.wiki]
+/// display_name = "Internal Wiki"
+/// url = "https://wiki.example.com/search?q={query}&start={offset}"
+/// results = ".search-result"
+/// result_title = "h3"
+/// result_url = "a"
+/// result_description = ".excerpt"
+/// no_results = ".empty-state"
+/// no_results_marker = "Nothing found"
+/// ```
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct HtmlEngineConfig {
+    /// It stores how the engine is shown and requested.
+    #[serde(flatten)]
+    pub engine: DeclarativeEngineConfig,
+    /// It stores the css selector of the element which is displayed if nothing was found.
+    pub no_results: String,
+    /// It stores the text which the no results element has to contain for the result set to be
+    /// considered empty. The presence of the element is enough if it is missing.
+    pub no_results_marker: Option<String>,
+    /// It stores the css selector of the elements containing one search result each.
+    pub results: String,
+    /// It stores the css selector of the title relative to a search result.
+    pub result_title: String,
+    /// It stores the css selector of the url relative to a search result. The `href` attribute of
+    /// the element is used if present, otherwise its text.
+    pub result_url: String,
+    /// It stores the css selector of the description relative to a search result.
+    pub result_description: String,
+}
+
+/// A named struct which describes an upstream search engine serving a json search api. The fields
+/// of the response are located with json pointers (e.g. `/data/items`), the empty pointer refers
+/// to the whole document.
+///
+/// # Example
+///
+/// ```toml
+/// [json_engines.gitea]
+/// display_name = "Gitea"
+/// url = "https://git.example.com/api/v1/repos/search?q={query}&page={page}&limit=10"
+/// results = "/data"
+/// result_title = "/full_name"
+/// result_url = "/html_url"
+/// result_description = "/description"
+/// ```
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct JsonEngineConfig {
+    /// It stores how the engine is shown and requested.
+    #[serde(flatten)]
+    pub engine: DeclarativeEngineConfig,
+    /// It stores the json pointer of the array of search results in the response.
+    pub results: String,
+    /// It stores the json pointer of the title relative to a search result.
+    pub result_title: String,
+    /// It stores the json pointer of the url relative to a search result.
+    pub result_url: String,
+    /// It stores the json pointer of the description relative to a search result, results have
+    /// an empty description if it is missing.
+    pub result_description: Option<String>,
+}
+
+/// A named struct which describes the instances of a meta search engine (like searx or librex)
+/// which can be queried. If an instance fails or answers with a captcha the next one is tried.
+///
+/// # Example
+///
+/// ```toml
+/// [librex]
+/// instances = ["https://search.ahwx.org", "https://librex.example.com"]
+/// selection = "random"
+/// ```
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InstancePoolConfig {
+    /// It stores the base urls of the instances.
+    pub instances: Vec<String>,
+    /// It stores how the first instance to query is selected.
+    #[serde(default)]
+    pub selection: InstanceSelection,
+}
+
+/// An enum type which provides the ways to select the first instance of an instance pool.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InstanceSelection {
+    /// This variant starts with the next instance for every request.
+    #[default]
+    RoundRobin,
+    /// This variant starts with a random instance for every request.
+    Random,
+}
+
+/// A named struct which describes the searx (or SearXNG) instances which are queried.
+///
+/// # Example
+///
+/// ```toml
+/// [searx]
+/// instances = ["https://searxng.internal.example.com"]
+/// format = "json"
+/// ```
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct SearxConfig {
+    /// It stores the instances and how they are selected.
+    #[serde(flatten)]
+    pub pool: InstancePoolConfig,
+    /// It stores the format in which the results are requested from the instances.
+    #[serde(default)]
+    pub format: SearxFormat,
+}
+
+/// An enum type which provides the formats in which the results are requested from searx.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SearxFormat {
+    /// This variant scrapes the html result page, which every public instance provides.
+    #[default]
+    Html,
+    /// This variant uses the json api of SearXNG, which has to be enabled in the `search.formats`
+    /// setting of the instance and is usually only available on self-hosted instances.
+    Json,
+}
+
+/// A named struct which describes how the requests of an upstream engine are timed out and
+/// retried. Only requests which failed with a request error or timed out are retried, the delay
+/// before a retry starts at `backoff` and is doubled after every retry.
+///
+/// # Example
+///
+/// ```toml
+/// [engine_policy]
+/// timeout = 1500
+/// retries = 1
+/// backoff = 200
+///
+/// [engine_policies.duckduckgo]
+/// timeout = 800
+/// ```
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub struct EnginePolicy {
+    /// It stores the time (millisecs) after which a request to the engine is aborted. It cannot
+    /// exceed the `request_timeout`.
+    pub timeout: u64,
+    /// It stores how often a failed request to the engine is retried.
+    pub retries: u8,
+    /// It stores the time (millisecs) waited before the first retry.
+    pub backoff: u64,
+}
+
+impl Default for EnginePolicy {
+    fn default() -> Self {
+        Self {
+            timeout: 2000,
+            retries: 0,
+            backoff: 250,
+        }
+    }
+}
+
+/// A named struct which describes the settings of an engine policy overridden for a single
+/// engine, the missing settings are taken from the default engine policy.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EnginePolicyOverride {
+    /// It stores the time (millisecs) after which a request to the engine is aborted. It cannot
+    /// exceed the `request_timeout`.
+    pub timeout: Option<u64>,
+    /// It stores how often a failed request to the engine is retried.
+    pub retries: Option<u8>,
+    /// It stores the time (millisecs) waited before the first retry.
+    pub backoff: Option<u64>,
+}
+
+/// A named struct which describes when an upstream engine whose requests keep failing is
+/// skipped. After `failure_threshold` consecutive failures the engine is skipped for `cool_down`
+/// seconds, then a single request probes it again: the engine is enabled again if it succeeds,
+/// otherwise it is skipped for another cool-down period.
+///
+/// # Example
+///
+/// ```toml
+/// [circuit_breaker]
+/// failure_threshold = 3
+/// cool_down = 120
+/// ```
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitBreakerConfig {
+    /// It stores the number of consecutive failures after which an engine is skipped, engines
+    /// are never skipped if it is 0.
+    pub failure_threshold: u32,
+    /// It stores the time (secs) for which a failing engine is skipped.
+    pub cool_down: u64,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            cool_down: 60,
+        }
+    }
+}
+
+/// Returns the default categories of a declarative engine.
+fn default_categories() -> Vec<String> {
+    vec!["general".to_owned()]
+}
+
+/// Returns the default number of results per page of a declarative engine.
+fn default_results_per_page() -> u32 {
+    10
 }
 
 /// Configuration options for the rate limiter middleware.
@@ -43,24 +356,279 @@ impl Default for Config {
         Self {
             port: 8080,
             binding_ip: "127.0.0.1".into(),
+            public_url: None,
             cache_expiry_time: 600,
+            suggestion_cache_expiry_time: 60,
             logging: true,
             debug: false,
             adaptive_window: false,
-            upstream_search_engines: Default::default(),
+            upstream_search_engines: crate::engines::EngineRegistry::default()
+                .names()
+                .map(String::from)
+                .collect(),
+            engine_weights: HashMap::new(),
+            html_engines: BTreeMap::new(),
+            json_engines: BTreeMap::new(),
+            searx: SearxConfig {
+                pool: InstancePoolConfig {
+                    instances: vec![crate::engines::searx::DEFAULT_INSTANCE.to_owned()],
+                    selection: InstanceSelection::default(),
+                },
+                format: SearxFormat::default(),
+            },
+            librex: InstancePoolConfig {
+                instances: vec![crate::engines::librex::DEFAULT_INSTANCE.to_owned()],
+                selection: InstanceSelection::default(),
+            },
+            engine_base_urls: BTreeMap::new(),
+            engine_policy: EnginePolicy::default(),
+            engine_policies: BTreeMap::new(),
+            circuit_breaker: CircuitBreakerConfig::default(),
+            health_window: 3600,
+            aggregation_deadline: None,
+            aggregation_quorum: None,
+            cache_late_results: true,
             request_timeout: 2,
             tcp_connection_keep_alive: 10,
             pool_idle_connection_timeout: 30,
+            https_only: true,
+            proxy: None,
+            engine_proxies: BTreeMap::new(),
         }
     }
 }
 
 impl Config {
-    /// Creates a new config based on the environment variables.
+    /// Creates a new config based on the config file (`config.toml`), if present, and the
+    /// environment variables, which take precedence over the config file.
     pub fn parse() -> Self {
-        Figment::from(Serialized::defaults(Config::default()))
-            .merge(figment::providers::Env::prefixed("SEARCH"))
-            .extract()
-            .unwrap()
+        let mut figment = Figment::from(Serialized::defaults(Config::default()));
+        if let Ok(path) = file_path(FileType::Config) {
+            figment = figment.merge(Toml::file(path));
+        }
+        let config: Config = figment.merge(Env::prefixed("SEARCH")).extract().unwrap();
+        // a bad proxy stops the startup instead of failing every request of its engines.
+        config.validate_proxies().unwrap();
+        config.validate_engine_weights().unwrap();
+        config
+    }
+
+    /// Checks that the weights of the engines are finite and not negative, as a negative or a
+    /// `NaN` weight would break the ordering of the ranked results.
+    ///
+    /// # Error
+    ///
+    /// Returns the error of the first invalid engine weight.
+    pub fn validate_engine_weights(&self) -> Result<(), String> {
+        for (engine, weight) in &self.engine_weights {
+            if !weight.is_finite() || *weight < 0.0 {
+                return Err(format!(
+                    "invalid weight {weight} of the engine '{engine}', it has to be a finite \
+                     number of at least 0"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the global proxy and the proxies of the engines are valid proxy urls (or the
+    /// `direct` setting).
+    ///
+    /// # Error
+    ///
+    /// Returns the error of the first invalid proxy url.
+    pub fn validate_proxies(&self) -> Result<(), String> {
+        let proxies = self.proxy.iter().chain(self.engine_proxies.values());
+        for proxy in proxies.filter(|proxy| proxy.as_str() != DIRECT) {
+            reqwest::Proxy::all(proxy)
+                .map_err(|error| format!("invalid proxy url '{proxy}': {error}"))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the base url under which the server is reachable, without a trailing slash. It
+    /// uses the configured public url if present, otherwise it is built from the scheme and the
+    /// host of the request, which honour the `Forwarded` and `X-Forwarded-*` headers set by a
+    /// reverse proxy.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection_info` - It takes the connection info of the current request.
+    pub fn base_url(&self, connection_info: &ConnectionInfo) -> String {
+        match &self.public_url {
+            Some(public_url) => public_url.trim_end_matches('/').to_owned(),
+            None => format!("{}://{}", connection_info.scheme(), connection_info.host()),
+        }
+    }
+
+    /// Returns the proxy url through which an upstream engine is requested, which is the proxy
+    /// override of the engine if present, otherwise the global proxy.
+    ///
+    /// # Arguments
+    ///
+    /// * `engine` - It takes the name of the engine.
+    pub fn proxy_for(&self, engine: &str) -> Option<&str> {
+        engine_setting(&self.engine_proxies, engine)
+            .or(self.proxy.as_ref())
+            .map(String::as_str)
+    }
+
+    /// Returns the policy of an upstream engine, which is the default engine policy with the
+    /// overrides configured for the engine applied. The timeout of the policy is capped by the
+    /// `request_timeout`.
+    ///
+    /// # Arguments
+    ///
+    /// * `engine` - It takes the name of the engine.
+    pub fn engine_policy_for(&self, engine: &str) -> EnginePolicy {
+        let default = self.engine_policy;
+        let policy = match engine_setting(&self.engine_policies, engine) {
+            Some(policy) => EnginePolicy {
+                timeout: policy.timeout.unwrap_or(default.timeout),
+                retries: policy.retries.unwrap_or(default.retries),
+                backoff: policy.backoff.unwrap_or(default.backoff),
+            },
+            None => default,
+        };
+        // the shared client aborts every request after the request timeout anyway.
+        EnginePolicy {
+            timeout: policy.timeout.min(u64::from(self.request_timeout) * 1000),
+            ..policy
+        }
+    }
+}
+
+/// Returns the setting of an engine from the settings keyed by engine names, like the engine
+/// weights or proxies. The name is matched exactly first and then case-insensitively, like the
+/// engines of the registry are selected.
+///
+/// # Arguments
+///
+/// * `settings` - It takes the settings keyed by engine names.
+/// * `engine` - It takes the name of the engine.
+pub fn engine_setting<'a, V>(
+    settings: impl IntoIterator<Item = (&'a String, &'a V)>,
+    engine: &str,
+) -> Option<&'a V> {
+    let mut case_insensitive = None;
+    for (name, setting) in settings {
+        if name == engine {
+            return Some(setting);
+        }
+        if case_insensitive.is_none() && name.eq_ignore_ascii_case(engine) {
+            case_insensitive = Some(setting);
+        }
+    }
+    case_insensitive
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_the_valid_proxies() {
+        let config = Config {
+            proxy: Some("socks5h://127.0.0.1:9050".to_owned()),
+            engine_proxies: BTreeMap::from([
+                (
+                    "bing".to_owned(),
+                    "http://proxy.example.com:3128".to_owned(),
+                ),
+                ("brave".to_owned(), DIRECT.to_owned()),
+            ]),
+            ..Config::default()
+        };
+
+        assert_eq!(config.validate_proxies(), Ok(()));
+    }
+
+    #[test]
+    fn rejects_an_invalid_proxy() {
+        let global = Config {
+            proxy: Some("not a proxy".to_owned()),
+            ..Config::default()
+        };
+        let engine = Config {
+            engine_proxies: BTreeMap::from([("bing".to_owned(), "http://[::1".to_owned())]),
+            ..Config::default()
+        };
+
+        assert!(global.validate_proxies().is_err());
+        assert!(engine.validate_proxies().is_err());
+    }
+
+    #[test]
+    fn rejects_the_invalid_engine_weights() {
+        let weights = |weight: f32| Config {
+            engine_weights: HashMap::from([("bing".to_owned(), weight)]),
+            ..Config::default()
+        };
+
+        assert_eq!(weights(0.0).validate_engine_weights(), Ok(()));
+        assert_eq!(weights(2.5).validate_engine_weights(), Ok(()));
+        assert!(weights(-1.0).validate_engine_weights().is_err());
+        assert!(weights(f32::NAN).validate_engine_weights().is_err());
+        assert!(weights(f32::INFINITY).validate_engine_weights().is_err());
+    }
+
+    #[test]
+    fn matches_the_engine_settings_case_insensitively() {
+        let config = Config {
+            engine_proxies: BTreeMap::from([
+                ("Bing".to_owned(), DIRECT.to_owned()),
+                ("MyWiki".to_owned(), "socks5h://127.0.0.1:9050".to_owned()),
+                (
+                    "mywiki".to_owned(),
+                    "http://proxy.example.com:3128".to_owned(),
+                ),
+            ]),
+            engine_policies: BTreeMap::from([(
+                "SEARX".to_owned(),
+                EnginePolicyOverride {
+                    retries: Some(3),
+                    ..EnginePolicyOverride::default()
+                },
+            )]),
+            ..Config::default()
+        };
+
+        assert_eq!(config.proxy_for("bing"), Some(DIRECT));
+        // the exact name takes precedence.
+        assert_eq!(
+            config.proxy_for("mywiki"),
+            Some("http://proxy.example.com:3128")
+        );
+        assert_eq!(config.engine_policy_for("searx").retries, 3);
+    }
+
+    #[test]
+    fn the_request_timeout_caps_the_policy_timeouts() {
+        let config = Config {
+            request_timeout: 2,
+            engine_policy: EnginePolicy {
+                timeout: 5000,
+                retries: 1,
+                backoff: 100,
+            },
+            engine_policies: BTreeMap::from([(
+                "searx".to_owned(),
+                EnginePolicyOverride {
+                    timeout: Some(800),
+                    ..EnginePolicyOverride::default()
+                },
+            )]),
+            ..Config::default()
+        };
+
+        assert_eq!(
+            config.engine_policy_for("bing"),
+            EnginePolicy {
+                timeout: 2000,
+                retries: 1,
+                backoff: 100,
+            }
+        );
+        assert_eq!(config.engine_policy_for("searx").timeout, 800);
     }
 }
@@ -2,42 +2,94 @@
 //! by querying the upstream bing search engine with user provided query and with a page
 //! number if provided.
 
-use std::collections::HashMap;
-
 use regex::Regex;
-use reqwest::header::HeaderMap;
 use reqwest::Client;
 use scraper::Html;
 
 use crate::models::aggregation_models::SearchResult;
 
 use crate::models::engine_models::{EngineError, SearchEngine};
+use crate::results::browser_profile::BrowserProfile;
 
-use error_stack::{Report, Result, ResultExt};
+use error_stack::{Report, Result};
 
 use super::search_result_parser::SearchResultParser;
+use super::suggestion_parser::parse_opensearch_suggestions;
+use super::url_builder::build_url;
+
+/// The base url of Bing which is queried unless the config file overrides it.
+pub const DEFAULT_BASE_URL: &str = "https://www.bing.com";
 
 /// A new Bing engine type defined in-order to implement the `SearchEngine` trait which allows to
 /// reduce code duplication as well as allows to create vector of different search engines easily.
 pub struct Bing {
     /// The parser, used to interpret the search result.
     parser: SearchResultParser,
+    /// The base url of the engine without a trailing slash.
+    base_url: String,
 }
 
 impl Default for Bing {
     /// Creates the Bing parser.
     fn default() -> Self {
+        Self::new(DEFAULT_BASE_URL)
+    }
+}
+
+impl Bing {
+    /// Creates the Bing engine querying the given base url.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - It takes the base url of the engine (e.g. `https://www.bing.com`).
+    pub fn new(base_url: &str) -> Self {
         Self {
             parser: SearchResultParser::new(
-                ".b_results",
+                "#b_results>.b_no",
                 ".b_algo",
                 "h2 a",
                 ".tpcn a.tilk",
                 ".b_caption p",
             )
             .expect("somehow you changed the static stings in the binary i guess"),
+            base_url: base_url.trim_end_matches('/').to_owned(),
         }
     }
+
+    /// Parses the html of a result page into the search results.
+    ///
+    /// # Arguments
+    ///
+    /// * `html` - It takes the html of the result page.
+    ///
+    /// # Error
+    ///
+    /// Returns an `EmptyResultSet` error if the page reports that there are no results.
+    pub fn parse_results(&self, html: &str) -> Result<Vec<(String, SearchResult)>, EngineError> {
+        let document: Html = Html::parse_document(html);
+
+        // Bing is very aggressive in finding matches even with the most absurd of queries, so
+        // it only shows the ".b_no" list item instead of the ".b_algo" results if nothing matched.
+        if self.parser.parse_for_no_results(&document).next().is_some() {
+            return Err(Report::new(EngineError::EmptyResultSet));
+        }
+
+        let re_span = Regex::new(r#"<span.*?>.*?(?:</span>&nbsp;·|</span>)"#).unwrap();
+        let re_strong = Regex::new(r#"(<strong>|</strong>)"#).unwrap();
+
+        // scrape all the results from the html
+        self.parser
+            .parse_for_results(&document, |title, url, desc| {
+                url.value().attr("href").map(|url| {
+                    SearchResult::new(
+                        &re_strong.replace_all(title.inner_html().trim(), ""),
+                        url.trim(),
+                        &re_span.replace_all(desc.inner_html().trim(), ""),
+                        &["bing"],
+                    )
+                })
+            })
+    }
 }
 
 #[async_trait::async_trait]
@@ -46,8 +98,9 @@ impl SearchEngine for Bing {
         &self,
         query: &str,
         page: u32,
-        user_agent: &str,
+        profile: &BrowserProfile,
         client: &Client,
+        safe_search: u8,
     ) -> Result<Vec<(String, SearchResult)>, EngineError> {
         // Bing uses `start results from this number` convention
         // So, for 10 results per page, page 0 starts at 1, page 1
@@ -55,13 +108,22 @@ impl SearchEngine for Bing {
         let results_per_page = 10;
         let start_result = results_per_page * page + 1;
 
+        let adult_filter = match safe_search {
+            0 => "off",
+            1 => "moderate",
+            _ => "strict",
+        };
+
+        let first = start_result.to_string();
         let url: String = match page {
-            0 => {
-                format!("https://www.bing.com/search?q={query}")
-            }
-            _ => {
-                format!("https://www.bing.com/search?q={query}&first={start_result}")
-            }
+            0 => build_url(
+                &format!("{}/search", self.base_url),
+                &[("q", query), ("adlt", adult_filter)],
+            )?,
+            _ => build_url(
+                &format!("{}/search", self.base_url),
+                &[("q", query), ("first", &first), ("adlt", adult_filter)],
+            )?,
         };
 
         let query_params: Vec<(&str, &str)> = vec![
@@ -78,47 +140,31 @@ impl SearchEngine for Bing {
             cookie_string.push_str(&format!("{k}={v}; "));
         }
 
-        let header_map = HeaderMap::try_from(&HashMap::from([
-            ("User-Agent".to_string(), user_agent.to_string()),
-            ("Referer".to_string(), "https://google.com/".to_string()),
-            (
-                "Content-Type".to_string(),
-                "application/x-www-form-urlencoded".to_string(),
-            ),
-            ("Cookie".to_string(), cookie_string),
-        ]))
-        .change_context(EngineError::UnexpectedError)?;
-
-        let document: Html = Html::parse_document(
-            &Bing::fetch_html_from_upstream(self, &url, header_map, client).await?,
-        );
-
-        // Bing is very aggressive in finding matches
-        // even with the most absurd of queries. ".b_algo" is the
-        // class for the list item of results
-        if let Some(no_result_msg) = self.parser.parse_for_no_results(&document).nth(0) {
-            if no_result_msg
-                .value()
-                .attr("class")
-                .map(|classes| classes.contains("b_algo"))
-                .unwrap_or(false)
-            {
-                return Err(Report::new(EngineError::EmptyResultSet));
-            }
-        }
+        let header_map = profile.header_map(&[
+            ("Content-Type", "application/x-www-form-urlencoded"),
+            ("Cookie", &cookie_string),
+        ])?;
 
-        let re_span = Regex::new(r#"<span.*?>.*?(?:</span>&nbsp;·|</span>)"#).unwrap();
-        let re_strong = Regex::new(r#"(<strong>|</strong>)"#).unwrap();
+        let html = Bing::fetch_html_from_upstream(self, &url, header_map, client).await?;
+        self.parse_results(&html)
+    }
 
-        // scrape all the results from the html
-        self.parser
-            .parse_for_results(&document, |title, url, desc| {
-                Some(SearchResult::new(
-                    &re_strong.replace_all(title.inner_html().trim(), ""),
-                    url.value().attr("href").unwrap(),
-                    &re_span.replace_all(desc.inner_html().trim(), ""),
-                    &["bing"],
-                ))
-            })
+    async fn suggestions(
+        &self,
+        query: &str,
+        profile: &BrowserProfile,
+        client: &Client,
+    ) -> Result<Vec<String>, EngineError> {
+        let url = build_url(
+            &format!("{}/osjson.aspx", self.base_url),
+            &[("query", query)],
+        )?;
+
+        let header_map = profile.header_map(&[])?;
+
+        parse_opensearch_suggestions(
+            &Bing::fetch_json_as_bytes_from_upstream(self, url.as_str(), header_map, client)
+                .await?,
+        )
     }
 }
@@ -2,27 +2,44 @@
 //! by querying the upstream brave search engine with user provided query and with a page
 //! number if provided.
 
-use std::collections::HashMap;
-
-use reqwest::{header::HeaderMap, Client};
+use reqwest::Client;
 use scraper::Html;
 
 use crate::models::aggregation_models::SearchResult;
-use error_stack::{Report, Result, ResultExt};
+use error_stack::{Report, Result};
 
 use crate::models::engine_models::{EngineError, SearchEngine};
+use crate::results::browser_profile::BrowserProfile;
 
 use super::search_result_parser::SearchResultParser;
+use super::suggestion_parser::parse_opensearch_suggestions;
+use super::url_builder::build_url;
+
+/// The base url of Brave which is queried unless the config file overrides it.
+pub const DEFAULT_BASE_URL: &str = "https://search.brave.com";
 
 /// Scrapes the results from the Brave search engine.  
 pub struct Brave {
     /// Utilises generic logic for parsing search results.
     parser: SearchResultParser,
+    /// The base url of the engine without a trailing slash.
+    base_url: String,
 }
 
 impl Default for Brave {
     /// Creates the Brave parser.
     fn default() -> Self {
+        Self::new(DEFAULT_BASE_URL)
+    }
+}
+
+impl Brave {
+    /// Creates the Brave engine querying the given base url.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - It takes the base url of the engine (e.g. `https://search.brave.com`).
+    pub fn new(base_url: &str) -> Self {
         Self {
             parser: SearchResultParser::new(
                 "#results h4",
@@ -32,35 +49,21 @@ impl Default for Brave {
                 ".snippet-description",
             )
             .expect("somehow you changed the static stings in the binary i guess"),
+            base_url: base_url.trim_end_matches('/').to_owned(),
         }
     }
-}
 
-#[async_trait::async_trait]
-impl SearchEngine for Brave {
-    async fn results(
-        &self,
-        query: &str,
-        page: u32,
-        user_agent: &str,
-        client: &Client,
-    ) -> Result<Vec<(String, SearchResult)>, EngineError> {
-        let url = format!("https://search.brave.com/search?q={query}&offset={page}");
-
-        let header_map = HeaderMap::try_from(&HashMap::from([
-            ("User-Agent".to_string(), user_agent.to_string()),
-            (
-                "Content-Type".to_string(),
-                "application/x-www-form-urlencoded".to_string(),
-            ),
-            ("Referer".to_string(), "https://google.com/".to_string()),
-            ("Cookie".to_string(), "safe_search=off".into()),
-        ]))
-        .change_context(EngineError::UnexpectedError)?;
-
-        let document: Html = Html::parse_document(
-            &Brave::fetch_html_from_upstream(self, &url, header_map, client).await?,
-        );
+    /// Parses the html of a result page into the search results.
+    ///
+    /// # Arguments
+    ///
+    /// * `html` - It takes the html of the result page.
+    ///
+    /// # Error
+    ///
+    /// Returns an `EmptyResultSet` error if the page reports that there are no results.
+    pub fn parse_results(&self, html: &str) -> Result<Vec<(String, SearchResult)>, EngineError> {
+        let document: Html = Html::parse_document(html);
 
         if let Some(no_result_msg) = self.parser.parse_for_no_results(&document).nth(0) {
             if no_result_msg
@@ -84,3 +87,53 @@ impl SearchEngine for Brave {
             })
     }
 }
+
+#[async_trait::async_trait]
+impl SearchEngine for Brave {
+    async fn results(
+        &self,
+        query: &str,
+        page: u32,
+        profile: &BrowserProfile,
+        client: &Client,
+        safe_search: u8,
+    ) -> Result<Vec<(String, SearchResult)>, EngineError> {
+        let url = build_url(
+            &format!("{}/search", self.base_url),
+            &[("q", query), ("offset", &page.to_string())],
+        )?;
+
+        let safe_search_level = match safe_search {
+            0 => "off",
+            1 => "moderate",
+            _ => "strict",
+        };
+
+        let header_map = profile.header_map(&[
+            ("Content-Type", "application/x-www-form-urlencoded"),
+            ("Cookie", &format!("safe_search={safe_search_level}")),
+        ])?;
+
+        let html = Brave::fetch_html_from_upstream(self, &url, header_map, client).await?;
+        self.parse_results(&html)
+    }
+
+    async fn suggestions(
+        &self,
+        query: &str,
+        profile: &BrowserProfile,
+        client: &Client,
+    ) -> Result<Vec<String>, EngineError> {
+        let url = build_url(
+            &format!("{}/api/suggest", self.base_url),
+            &[("q", query), ("rich", "false")],
+        )?;
+
+        let header_map = profile.header_map(&[])?;
+
+        parse_opensearch_suggestions(
+            &Brave::fetch_json_as_bytes_from_upstream(self, url.as_str(), header_map, client)
+                .await?,
+        )
+    }
+}
@@ -0,0 +1,113 @@
+//! This module provides the request handling shared by the declarative engines defined in the
+//! config file, like filling the url template and building the request headers.
+
+use std::collections::HashMap;
+
+use error_stack::{Report, Result};
+use reqwest::{header::HeaderMap, Url};
+
+use crate::config::DeclarativeEngineConfig;
+use crate::models::engine_models::{EngineError, EngineInfo};
+use crate::results::browser_profile::BrowserProfile;
+
+use super::url_builder::encode_query;
+
+/// A named struct which stores how a declarative engine is requested.
+pub struct DeclarativeRequest {
+    /// The url template of the search page.
+    url: String,
+    /// The number of results per page, used to compute the offset of a page.
+    results_per_page: u32,
+    /// The headers sent to the engine, including the cookies.
+    headers: HashMap<String, String>,
+}
+
+impl DeclarativeRequest {
+    /// Creates the request description from the config of a declarative engine.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - It takes the shared part of the declarative engine config.
+    ///
+    /// # Error
+    ///
+    /// Returns an `EngineError` if the filled url template is not a valid url.
+    pub fn new(config: &DeclarativeEngineConfig) -> Result<Self, EngineError> {
+        let mut headers: HashMap<String, String> = config.headers.clone().into_iter().collect();
+        if !config.cookies.is_empty() {
+            let cookies: Vec<String> = config
+                .cookies
+                .iter()
+                .map(|(key, value)| format!("{key}={value}"))
+                .collect();
+            headers.insert("Cookie".to_owned(), cookies.join("; "));
+        }
+
+        let request = Self {
+            url: config.url.clone(),
+            results_per_page: config.results_per_page,
+            headers,
+        };
+        // the placeholders are filled with url safe values, so a template which is a valid url
+        // once filled is valid for every query.
+        if let Err(error) = Url::parse(&request.url("", 0, 0)) {
+            return Err(Report::new(EngineError::UnexpectedError)
+                .attach_printable(format!("invalid url template {}: {error}", config.url)));
+        }
+        Ok(request)
+    }
+
+    /// Returns the metadata of the engine for the engine registry. Paging and safe search are
+    /// supported if the url template contains the corresponding placeholders.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - It takes the name of the engine.
+    /// * `config` - It takes the shared part of the declarative engine config.
+    pub fn info(&self, name: &str, config: &DeclarativeEngineConfig) -> EngineInfo {
+        EngineInfo {
+            display_name: config
+                .display_name
+                .clone()
+                .unwrap_or_else(|| name.to_owned()),
+            categories: config.categories.clone(),
+            supports_paging: self.url.contains("{page}") || self.url.contains("{offset}"),
+            supports_safe_search: self.url.contains("{safe_search}"),
+        }
+    }
+
+    /// Fills the url template with the encoded query, the page number, the offset of the page
+    /// and the safe search level.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - It takes the user provided query.
+    /// * `page` - It takes the page number (starting from 0).
+    /// * `safe_search` - It takes the safe search level.
+    pub fn url(&self, query: &str, page: u32, safe_search: u8) -> String {
+        self.url
+            .replace("{query}", &encode_query(query))
+            .replace("{page}", &(page + 1).to_string())
+            .replace("{offset}", &(page * self.results_per_page).to_string())
+            .replace("{safe_search}", &safe_search.to_string())
+    }
+
+    /// Builds the request headers from the headers of the browser profile, which are replaced by
+    /// the headers the config sets.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - It takes a random browser profile.
+    ///
+    /// # Error
+    ///
+    /// Returns an `EngineError` if a configured header is not a valid http header.
+    pub fn header_map(&self, profile: &BrowserProfile) -> Result<HeaderMap, EngineError> {
+        let headers: Vec<(&str, &str)> = self
+            .headers
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect();
+        profile.header_map(&headers)
+    }
+}
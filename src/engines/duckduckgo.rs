@@ -2,30 +2,46 @@
 //! by querying the upstream duckduckgo search engine with user provided query and with a page
 //! number if provided.
 
-use std::collections::HashMap;
-
-use reqwest::header::HeaderMap;
 use reqwest::Client;
 use scraper::Html;
 
 use crate::models::aggregation_models::SearchResult;
 
 use crate::models::engine_models::{EngineError, SearchEngine};
+use crate::results::browser_profile::BrowserProfile;
 
-use error_stack::{Report, Result, ResultExt};
+use error_stack::{Report, Result};
 
 use super::search_result_parser::SearchResultParser;
+use super::suggestion_parser::parse_opensearch_suggestions;
+use super::url_builder::build_url;
+
+/// The base url of DuckDuckGo which is queried unless the config file overrides it.
+pub const DEFAULT_BASE_URL: &str = "https://html.duckduckgo.com";
 
 /// A new DuckDuckGo engine type defined in-order to implement the `SearchEngine` trait which allows to
 /// reduce code duplication as well as allows to create vector of different search engines easily.
 pub struct DuckDuckGo {
     /// The parser, used to interpret the search result.
     parser: SearchResultParser,
+    /// The base url of the engine without a trailing slash.
+    base_url: String,
 }
 
 impl Default for DuckDuckGo {
     /// Creates the DuckDuckGo parser.
     fn default() -> Self {
+        Self::new(DEFAULT_BASE_URL)
+    }
+}
+
+impl DuckDuckGo {
+    /// Creates the DuckDuckGo engine querying the given base url.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - It takes the base url of the engine (e.g. `https://html.duckduckgo.com`).
+    pub fn new(base_url: &str) -> Self {
         Self {
             parser: SearchResultParser::new(
                 ".no-results",
@@ -35,7 +51,36 @@ impl Default for DuckDuckGo {
                 ".result__snippet",
             )
             .expect("somehow you changed the static stings in the binary i guess"),
+            base_url: base_url.trim_end_matches('/').to_owned(),
+        }
+    }
+
+    /// Parses the html of a result page into the search results.
+    ///
+    /// # Arguments
+    ///
+    /// * `html` - It takes the html of the result page.
+    ///
+    /// # Error
+    ///
+    /// Returns an `EmptyResultSet` error if the page reports that there are no results.
+    pub fn parse_results(&self, html: &str) -> Result<Vec<(String, SearchResult)>, EngineError> {
+        let document: Html = Html::parse_document(html);
+
+        if self.parser.parse_for_no_results(&document).next().is_some() {
+            return Err(Report::new(EngineError::EmptyResultSet));
         }
+
+        // scrape all the results from the html
+        self.parser
+            .parse_for_results(&document, |title, url, desc| {
+                Some(SearchResult::new(
+                    title.inner_html().trim(),
+                    &format!("https://{}", url.inner_html().trim()),
+                    desc.inner_html().trim(),
+                    &["duckduckgo"],
+                ))
+            })
     }
 }
 
@@ -45,53 +90,71 @@ impl SearchEngine for DuckDuckGo {
         &self,
         query: &str,
         page: u32,
-        user_agent: &str,
+        profile: &BrowserProfile,
         client: &Client,
+        safe_search: u8,
     ) -> Result<Vec<(String, SearchResult)>, EngineError> {
+        // DuckDuckGo uses `-2` for off, `-1` for moderate and `1` for strict safe search.
+        let safe_search_level = match safe_search {
+            0 => -2,
+            1 => -1,
+            _ => 1,
+        };
+
         // Page number can be missing or empty string and so appropriate handling is required
         // so that upstream server recieves valid page number.
+        let safe_search_level = safe_search_level.to_string();
         let url: String = match page {
-            0 => {
-                format!("https://html.duckduckgo.com/html/?q={query}&s=&dc=&v=1&o=json&api=/d.js")
-            }
-            _ => {
-                format!(
-                    "https://duckduckgo.com/html/?q={query}&s={}&dc={}&v=1&o=json&api=/d.js",
-                    page * 30,
-                    page * 30 + 1
-                )
-            }
+            0 => build_url(
+                &format!("{}/html/", self.base_url),
+                &[
+                    ("q", query),
+                    ("v", "1"),
+                    ("o", "json"),
+                    ("api", "/d.js"),
+                    ("kp", &safe_search_level),
+                ],
+            )?,
+            _ => build_url(
+                &format!("{}/html/", self.base_url),
+                &[
+                    ("q", query),
+                    ("s", &(page * 30).to_string()),
+                    ("dc", &(page * 30 + 1).to_string()),
+                    ("v", "1"),
+                    ("o", "json"),
+                    ("api", "/d.js"),
+                    ("kp", &safe_search_level),
+                ],
+            )?,
         };
 
-        // initializing HeaderMap and adding appropriate headers.
-        let header_map = HeaderMap::try_from(&HashMap::from([
-            ("User-Agent".to_string(), user_agent.to_string()),
-            ("Referer".to_string(), "https://google.com/".to_string()),
-            (
-                "Content-Type".to_string(),
-                "application/x-www-form-urlencoded".to_string(),
-            ),
-            ("Cookie".to_string(), "kl=wt-wt".to_string()),
-        ]))
-        .change_context(EngineError::UnexpectedError)?;
-
-        let document: Html = Html::parse_document(
-            &DuckDuckGo::fetch_html_from_upstream(self, &url, header_map, client).await?,
-        );
+        // adding the engine specific headers to the headers of the browser profile.
+        let header_map = profile.header_map(&[
+            ("Content-Type", "application/x-www-form-urlencoded"),
+            ("Cookie", "kl=wt-wt"),
+        ])?;
 
-        if self.parser.parse_for_no_results(&document).next().is_some() {
-            return Err(Report::new(EngineError::EmptyResultSet));
-        }
+        let html = DuckDuckGo::fetch_html_from_upstream(self, &url, header_map, client).await?;
+        self.parse_results(&html)
+    }
 
-        // scrape all the results from the html
-        self.parser
-            .parse_for_results(&document, |title, url, desc| {
-                Some(SearchResult::new(
-                    title.inner_html().trim(),
-                    &format!("https://{}", url.inner_html().trim()),
-                    desc.inner_html().trim(),
-                    &["duckduckgo"],
-                ))
-            })
+    async fn suggestions(
+        &self,
+        query: &str,
+        profile: &BrowserProfile,
+        client: &Client,
+    ) -> Result<Vec<String>, EngineError> {
+        let url = build_url(
+            &format!("{}/ac/", self.base_url),
+            &[("q", query), ("type", "list")],
+        )?;
+
+        let header_map = profile.header_map(&[])?;
+
+        parse_opensearch_suggestions(
+            &DuckDuckGo::fetch_json_as_bytes_from_upstream(self, url.as_str(), header_map, client)
+                .await?,
+        )
     }
 }
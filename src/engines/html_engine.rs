@@ -0,0 +1,138 @@
+//! The `html_engine` module handles the scraping of results from the declarative html engines
+//! defined in the config file, by filling the configured url template with the user provided
+//! query and page number and parsing the result page with the configured css selectors.
+
+use reqwest::{Client, Url};
+use scraper::{ElementRef, Html};
+
+use crate::config::HtmlEngineConfig;
+use crate::models::aggregation_models::SearchResult;
+use error_stack::{Report, Result, ResultExt};
+
+use crate::models::engine_models::{EngineError, EngineInfo, SearchEngine};
+use crate::results::browser_profile::BrowserProfile;
+
+use super::declarative::DeclarativeRequest;
+use super::search_result_parser::{escape_text, result_url, SearchResultParser};
+
+/// Scrapes the results from an upstream search engine described in the config file.
+pub struct HtmlEngine {
+    /// The name of the engine, which is stored in the scraped results.
+    name: String,
+    /// Describes how the engine is requested.
+    request: DeclarativeRequest,
+    /// The metadata of the engine.
+    info: EngineInfo,
+    /// The text which the no results element has to contain for an empty result set.
+    no_results_marker: Option<String>,
+    /// Utilises generic logic for parsing search results.
+    parser: SearchResultParser,
+}
+
+impl HtmlEngine {
+    /// Creates the engine from its description in the config file.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - It takes the name of the engine.
+    /// * `config` - It takes the description of the engine from the config file.
+    ///
+    /// # Error
+    ///
+    /// Returns an `EngineError` if any of the css selectors or the url template is invalid.
+    pub fn new(name: &str, config: &HtmlEngineConfig) -> Result<Self, EngineError> {
+        let parser = SearchResultParser::new(
+            &config.no_results,
+            &config.results,
+            &config.result_title,
+            &config.result_url,
+            &config.result_description,
+        )
+        .attach_printable_lazy(|| format!("invalid selector in the engine '{name}'"))?;
+
+        let request = DeclarativeRequest::new(&config.engine)
+            .attach_printable_lazy(|| format!("invalid url in the engine '{name}'"))?;
+        Ok(Self {
+            name: name.to_owned(),
+            info: request.info(name, &config.engine),
+            request,
+            no_results_marker: config.no_results_marker.clone(),
+            parser,
+        })
+    }
+
+    /// Returns the metadata of the engine for the engine registry.
+    pub fn info(&self) -> &EngineInfo {
+        &self.info
+    }
+
+    /// Parses the html of a result page into the search results.
+    ///
+    /// # Arguments
+    ///
+    /// * `html` - It takes the html of the result page.
+    /// * `base_url` - It takes the url of the result page, against which relative urls are
+    ///   resolved.
+    ///
+    /// # Error
+    ///
+    /// Returns an `EmptyResultSet` error if the page reports that there are no results.
+    pub fn parse_results(
+        &self,
+        html: &str,
+        base_url: &Url,
+    ) -> Result<Vec<(String, SearchResult)>, EngineError> {
+        let document: Html = Html::parse_document(html);
+
+        if let Some(no_result_msg) = self.parser.parse_for_no_results(&document).next() {
+            let is_empty = match &self.no_results_marker {
+                Some(marker) => no_result_msg.text().collect::<String>().contains(marker),
+                None => true,
+            };
+            if is_empty {
+                return Err(Report::new(EngineError::EmptyResultSet));
+            }
+        }
+
+        // scrape all the results from the html, resolving relative urls against the search page.
+        self.parser
+            .parse_for_results(&document, |title, url, desc| {
+                let href = url
+                    .value()
+                    .attr("href")
+                    .map(str::to_owned)
+                    .unwrap_or_else(|| url.text().collect());
+                let url = result_url(base_url, &href)?;
+                Some(SearchResult::new(
+                    &element_text(title),
+                    url.as_str(),
+                    &element_text(desc),
+                    &[&self.name],
+                ))
+            })
+    }
+}
+
+/// Returns the trimmed text content of an element, escaped as it is rendered as html.
+fn element_text(element: &ElementRef<'_>) -> String {
+    escape_text(element.text().collect::<String>().trim())
+}
+
+#[async_trait::async_trait]
+impl SearchEngine for HtmlEngine {
+    async fn results(
+        &self,
+        query: &str,
+        page: u32,
+        profile: &BrowserProfile,
+        client: &Client,
+        safe_search: u8,
+    ) -> Result<Vec<(String, SearchResult)>, EngineError> {
+        let url = self.request.url(query, page, safe_search);
+        let base_url = Url::parse(&url).change_context(EngineError::UnexpectedError)?;
+        let header_map = self.request.header_map(profile)?;
+
+        let html = HtmlEngine::fetch_html_from_upstream(self, &url, header_map, client).await?;
+        self.parse_results(&html, &base_url)
+    }
+}
@@ -0,0 +1,96 @@
+//! This module provides the instance pools of the meta search engines (like searx and librex),
+//! which select the instance to query and fail over to the next instance if an instance is down or
+//! answers with a captcha.
+
+use std::{
+    future::Future,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use error_stack::{Report, Result};
+
+use crate::config::{InstancePoolConfig, InstanceSelection};
+use crate::models::engine_models::EngineError;
+
+/// A pool of instances of a meta search engine.
+pub struct InstancePool {
+    /// The base urls of the instances without a trailing slash.
+    instances: Vec<String>,
+    /// How the first instance to query is selected.
+    selection: InstanceSelection,
+    /// The counter used for the round robin selection.
+    next: AtomicUsize,
+}
+
+impl InstancePool {
+    /// Creates the pool from its config.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - It takes the instance pool config of the meta search engine.
+    pub fn new(config: &InstancePoolConfig) -> Self {
+        Self {
+            instances: config
+                .instances
+                .iter()
+                .map(|instance| instance.trim_end_matches('/').to_owned())
+                .collect(),
+            selection: config.selection,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns the index of the first instance to query for a request.
+    fn first(&self) -> usize {
+        let index = match self.selection {
+            InstanceSelection::RoundRobin => self.next.fetch_add(1, Ordering::Relaxed),
+            InstanceSelection::Random => fastrand::usize(..self.instances.len().max(1)),
+        };
+        index % self.instances.len().max(1)
+    }
+
+    /// Calls the request function with the selected instance and fails over to the following
+    /// instances (wrapping around) until an instance provides an answer. An `EmptyResultSet` is
+    /// considered an answer, every other error causes a fail over.
+    ///
+    /// # Arguments
+    ///
+    /// * `engine` - It takes the name of the engine, which is used in the logs.
+    /// * `request` - It takes the function requesting the results from the base url of an
+    ///   instance.
+    ///
+    /// # Error
+    ///
+    /// Returns the error of the last instance if no instance provided an answer.
+    pub async fn failover<'a, T, F, Fut>(
+        &'a self,
+        engine: &str,
+        mut request: F,
+    ) -> Result<T, EngineError>
+    where
+        F: FnMut(&'a str) -> Fut,
+        Fut: Future<Output = Result<T, EngineError>>,
+    {
+        let mut last_error = Report::new(EngineError::RequestError)
+            .attach_printable(format!("no instances are configured for {engine}"));
+
+        let first = self.first();
+        let ordered = self.instances[first..]
+            .iter()
+            .chain(&self.instances[..first]);
+        for instance in ordered {
+            match request(instance).await {
+                Err(error) if !matches!(error.current_context(), EngineError::EmptyResultSet) => {
+                    log::warn!(
+                        "The {engine} instance {instance} failed ({}): {error:?}",
+                        error.current_context().kind()
+                    );
+                    last_error = error;
+                }
+                answer => return answer,
+            }
+        }
+
+        Err(last_error)
+    }
+}
@@ -0,0 +1,167 @@
+//! The `json_engine` module handles the fetching of results from the declarative json api engines
+//! defined in the config file, by filling the configured url template with the user provided
+//! query and page number and mapping the fields of the response with the configured json
+//! pointers.
+
+use reqwest::{Client, Url};
+use serde_json::Value;
+
+use crate::config::JsonEngineConfig;
+use crate::models::aggregation_models::SearchResult;
+use error_stack::{Report, Result, ResultExt};
+
+use crate::models::engine_models::{EngineError, EngineInfo, SearchEngine};
+use crate::results::browser_profile::BrowserProfile;
+
+use super::declarative::DeclarativeRequest;
+use super::search_result_parser::{escape_text, result_url};
+
+/// Fetches the results from an upstream json search api described in the config file.
+pub struct JsonEngine {
+    /// The name of the engine, which is stored in the results.
+    name: String,
+    /// Describes how the engine is requested.
+    request: DeclarativeRequest,
+    /// The metadata of the engine.
+    info: EngineInfo,
+    /// The json pointer of the array of search results in the response.
+    results: String,
+    /// The json pointer of the title relative to a search result.
+    result_title: String,
+    /// The json pointer of the url relative to a search result.
+    result_url: String,
+    /// The json pointer of the description relative to a search result.
+    result_description: Option<String>,
+}
+
+impl JsonEngine {
+    /// Creates the engine from its description in the config file.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - It takes the name of the engine.
+    /// * `config` - It takes the description of the engine from the config file.
+    ///
+    /// # Error
+    ///
+    /// Returns an `EngineError` if any of the json pointers or the url template is invalid.
+    pub fn new(name: &str, config: &JsonEngineConfig) -> Result<Self, EngineError> {
+        let pointers = [
+            Some(&config.results),
+            Some(&config.result_title),
+            Some(&config.result_url),
+            config.result_description.as_ref(),
+        ];
+        for pointer in pointers.into_iter().flatten() {
+            if !pointer.is_empty() && !pointer.starts_with('/') {
+                return Err(Report::new(EngineError::UnexpectedError).attach_printable(format!(
+                    "invalid json pointer in the engine '{name}': {pointer}, it has to be empty or start with '/'"
+                )));
+            }
+        }
+
+        let request = DeclarativeRequest::new(&config.engine)
+            .attach_printable_lazy(|| format!("invalid url in the engine '{name}'"))?;
+        Ok(Self {
+            name: name.to_owned(),
+            info: request.info(name, &config.engine),
+            request,
+            results: config.results.clone(),
+            result_title: config.result_title.clone(),
+            result_url: config.result_url.clone(),
+            result_description: config.result_description.clone(),
+        })
+    }
+
+    /// Returns the metadata of the engine for the engine registry.
+    pub fn info(&self) -> &EngineInfo {
+        &self.info
+    }
+
+    /// Maps a single search result of the response to a `SearchResult`. Results without a title
+    /// or an http(s) url are skipped, and the title and the description are escaped as they are
+    /// rendered as html.
+    ///
+    /// # Arguments
+    ///
+    /// * `result` - It takes the json value of the search result.
+    /// * `base_url` - It takes the url of the request, against which relative urls are resolved.
+    fn search_result(&self, result: &Value, base_url: &Url) -> Option<SearchResult> {
+        let field = |pointer: &str| result.pointer(pointer).and_then(Value::as_str);
+
+        let title = field(&self.result_title)?;
+        let url = result_url(base_url, field(&self.result_url)?)?;
+        let description = self
+            .result_description
+            .as_deref()
+            .and_then(field)
+            .unwrap_or_default();
+
+        Some(SearchResult::new(
+            &escape_text(title.trim()),
+            url.as_str(),
+            &escape_text(description.trim()),
+            &[&self.name],
+        ))
+    }
+
+    /// Parses the json response of the search api into the search results.
+    ///
+    /// # Arguments
+    ///
+    /// * `body` - It takes the body of the json response.
+    /// * `base_url` - It takes the url of the request, against which relative urls are resolved.
+    ///
+    /// # Error
+    ///
+    /// Returns an `EmptyResultSet` error if the result array is empty and a `ParseFailure` if the
+    /// response is not valid json or contains no result array.
+    pub fn parse_results(
+        &self,
+        body: &[u8],
+        base_url: &Url,
+    ) -> Result<Vec<(String, SearchResult)>, EngineError> {
+        let response: Value =
+            serde_json::from_slice(body).change_context(EngineError::ParseFailure)?;
+
+        let results = response
+            .pointer(&self.results)
+            .and_then(Value::as_array)
+            .ok_or_else(|| {
+                Report::new(EngineError::ParseFailure).attach_printable(format!(
+                    "the response contains no result array at '{}'",
+                    self.results
+                ))
+            })?;
+
+        if results.is_empty() {
+            return Err(Report::new(EngineError::EmptyResultSet));
+        }
+
+        Ok(results
+            .iter()
+            .filter_map(|result| self.search_result(result, base_url))
+            .map(|search_result| (search_result.url.clone(), search_result))
+            .collect())
+    }
+}
+
+#[async_trait::async_trait]
+impl SearchEngine for JsonEngine {
+    async fn results(
+        &self,
+        query: &str,
+        page: u32,
+        profile: &BrowserProfile,
+        client: &Client,
+        safe_search: u8,
+    ) -> Result<Vec<(String, SearchResult)>, EngineError> {
+        let url = self.request.url(query, page, safe_search);
+        let base_url = Url::parse(&url).change_context(EngineError::UnexpectedError)?;
+        let header_map = self.request.header_map(profile)?;
+
+        let body =
+            JsonEngine::fetch_json_as_bytes_from_upstream(self, &url, header_map, client).await?;
+        self.parse_results(&body, &base_url)
+    }
+}
@@ -1,56 +1,130 @@
 //! The `librex` module contains the implementation of a search engine for LibreX using the reqwest and scraper libraries.
 //! It includes a `SearchEngine` trait implementation for interacting with the search engine and retrieving search results.
 
-use std::collections::HashMap;
-
-use reqwest::header::HeaderMap;
 use reqwest::Client;
 use scraper::Html;
 
+use crate::config::{InstancePoolConfig, InstanceSelection};
 use crate::models::aggregation_models::SearchResult;
 use crate::models::engine_models::{EngineError, SearchEngine};
+use crate::results::browser_profile::BrowserProfile;
 
-use error_stack::{Report, Result, ResultExt};
+use error_stack::{Report, Result};
 
+use super::instance_pool::InstancePool;
 use super::search_result_parser::SearchResultParser;
+use super::url_builder::build_url;
+
+/// The LibreX instance which is queried if no instances are configured.
+pub const DEFAULT_INSTANCE: &str = "https://search.ahwx.org";
 
 /// Represents the LibreX search engine.
 pub struct LibreX {
     /// The parser used to extract search results from HTML documents.
     parser: SearchResultParser,
+    /// The instances which are queried.
+    pool: InstancePool,
 }
 
 impl Default for LibreX {
-    /// Creates a new instance of LibreX with a default configuration.
+    /// Creates a new instance of LibreX querying the default instance.
+    fn default() -> Self {
+        Self::new(&InstancePoolConfig {
+            instances: vec![DEFAULT_INSTANCE.to_owned()],
+            selection: InstanceSelection::default(),
+        })
+    }
+}
+
+impl LibreX {
+    /// Creates a new instance of LibreX querying the configured instances.
     ///
-    /// # Returns
+    /// # Arguments
     ///
-    /// Returns a `Result` containing `LibreX` if successful, otherwise an `EngineError`.
-    fn default() -> Self {
+    /// * `config` - It takes the instance pool config of LibreX.
+    pub fn new(config: &InstancePoolConfig) -> Self {
         Self {
             parser: SearchResultParser::new(
                 ".text-result-container>p",
-                ".text-result-container",
-                ".text-result-wrapper>a>h2",
-                ".text-result-wrapper>a",
-                ".text-result-wrapper>span",
+                ".text-result-wrapper",
+                "a>h2",
+                "a",
+                "span",
             )
             .expect("somehow you changed the static stings in the binary i guess"),
+            pool: InstancePool::new(config),
         }
     }
+
+    /// Scrapes the results from the result page of an instance.
+    async fn instance_results(
+        &self,
+        instance: &str,
+        query: &str,
+        page: u32,
+        profile: &BrowserProfile,
+        client: &Client,
+        safe_search_level: &str,
+    ) -> Result<Vec<(String, SearchResult)>, EngineError> {
+        // Page number can be missing or empty string and so appropriate handling is required
+        // so that upstream server recieves valid page number.
+        let url: String = build_url(
+            &format!("{instance}/search.php"),
+            &[("q", query), ("p", &(page * 10).to_string()), ("t", "10")],
+        )?;
+
+        // adding the engine specific headers to the headers of the browser profile.
+        let header_map = profile.header_map(&[
+            ("Content-Type", "application/x-www-form-urlencoded"),
+            ("Cookie", &format!("theme=amoled; disable_special=on; disable_frontends=on; language=en; number_of_results=10; safe_search={safe_search_level}; save=1")),
+        ])?;
+
+        let html = LibreX::fetch_html_from_upstream(self, &url, header_map, client).await?;
+        self.parse_results(&html)
+    }
+
+    /// Parses the html of a result page into the search results.
+    ///
+    /// # Arguments
+    ///
+    /// * `html` - It takes the html of the result page.
+    ///
+    /// # Error
+    ///
+    /// Returns an `EmptyResultSet` error if the page reports that there are no results.
+    pub fn parse_results(&self, html: &str) -> Result<Vec<(String, SearchResult)>, EngineError> {
+        let document: Html = Html::parse_document(html);
+
+        if self.parser.parse_for_no_results(&document).next().is_some() {
+            return Err(Report::new(EngineError::EmptyResultSet));
+        }
+
+        // scrape all the results from the html
+        self.parser
+            .parse_for_results(&document, |title, url, desc| {
+                url.value().attr("href").map(|url| {
+                    SearchResult::new(
+                        title.inner_html().trim(),
+                        url.trim(),
+                        desc.inner_html().trim(),
+                        &["librex"],
+                    )
+                })
+            })
+    }
 }
 
 #[async_trait::async_trait]
 impl SearchEngine for LibreX {
-    /// Retrieves search results from LibreX based on the provided query, page, user agent, and client.
+    /// Retrieves search results from LibreX based on the provided query, page, browser profile, and client.
     ///
     /// # Arguments
     ///
     /// * `query` - The search query.
     /// * `page` - The page number for pagination.
-    /// * `user_agent` - The user agent string.
+    /// * `profile` - The browser profile whose headers are sent.
     /// * `client` - The reqwest client for making HTTP requests.
-    /// * `_safe_search` - A parameter for safe search (not currently used).
+    /// * `safe_search` - The safe search level, LibreX only supports turning it on or off.
     ///
     /// # Returns
     ///
@@ -60,45 +134,19 @@ impl SearchEngine for LibreX {
         &self,
         query: &str,
         page: u32,
-        user_agent: &str,
+        profile: &BrowserProfile,
         client: &Client,
+        safe_search: u8,
     ) -> Result<Vec<(String, SearchResult)>, EngineError> {
-        // Page number can be missing or empty string and so appropriate handling is required
-        // so that upstream server recieves valid page number.
-        let url: String = format!(
-            "https://search.ahwx.org/search.php?q={query}&p={}&t=10",
-            page * 10
-        );
-
-        // initializing HeaderMap and adding appropriate headers.
-        let header_map = HeaderMap::try_from(&HashMap::from([
-            ("User-Agent".to_string(), user_agent.to_string()),
-            ("Referer".to_string(), "https://google.com/".to_string()),
-            ("Content-Type".to_string(), "application/x-www-form-urlencoded".to_string()),
-            (
-                "Cookie".to_string(),
-                "theme=amoled; disable_special=on; disable_frontends=on; language=en; number_of_results=10; safe_search=on; save=1".to_string(),
-            ),
-        ]))
-        .change_context(EngineError::UnexpectedError)?;
-
-        let document: Html = Html::parse_document(
-            &LibreX::fetch_html_from_upstream(self, &url, header_map, client).await?,
-        );
+        let safe_search_level = match safe_search {
+            0 => "off",
+            _ => "on",
+        };
 
-        if self.parser.parse_for_no_results(&document).next().is_some() {
-            return Err(Report::new(EngineError::EmptyResultSet));
-        }
-
-        // scrape all the results from the html
-        self.parser
-            .parse_for_results(&document, |title, url, desc| {
-                Some(SearchResult::new(
-                    title.inner_html().trim(),
-                    url.inner_html().trim(),
-                    desc.inner_html().trim(),
-                    &["librex"],
-                ))
+        self.pool
+            .failover("librex", |instance| {
+                self.instance_results(instance, query, page, profile, client, safe_search_level)
             })
+            .await
     }
 }
@@ -5,91 +5,199 @@
 
 use std::sync::Arc;
 
-use serde::{Deserialize, Serialize};
+use indexmap::IndexMap;
 
-use crate::models::engine_models::EngineHandler;
+use crate::config::Config;
+use crate::models::{
+    aggregation_models::EngineErrorInfo,
+    engine_models::{EngineError, EngineHandler, EngineInfo, SearchEngine},
+};
 
 pub mod bing;
 pub mod brave;
+pub mod declarative;
 pub mod duckduckgo;
+pub mod html_engine;
+pub mod instance_pool;
+pub mod json_engine;
 pub mod librex;
 pub mod mojeek;
 pub mod search_result_parser;
 pub mod searx;
 pub mod startpage;
+pub mod suggestion_parser;
+pub mod url_builder;
 
-/// Struct that keeps track of search engines
-#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
-pub struct Engines {
-    bing: bool,
-    brave: bool,
-    duckduckgo: bool,
-    librex: bool,
-    mojeek: bool,
-    searx: bool,
-    startpage: bool,
+/// A registry of all the upstream search engines known to the server, keyed by the engine name.
+/// The registry keeps the engines in the order in which they were registered.
+#[derive(Clone)]
+pub struct EngineRegistry {
+    /// It stores the engine handlers keyed by their engine name.
+    engines: IndexMap<String, EngineHandler>,
 }
 
-impl Default for Engines {
+impl Default for EngineRegistry {
+    /// Creates a registry containing all the built-in upstream search engines.
     fn default() -> Self {
-        Self {
-            bing: true,
-            brave: true,
-            duckduckgo: true,
-            librex: true,
-            mojeek: true,
-            searx: true,
-            startpage: true,
-        }
+        let mut registry = Self {
+            engines: IndexMap::new(),
+        };
+        registry.register_builtin(
+            "duckduckgo",
+            "DuckDuckGo",
+            duckduckgo::DuckDuckGo::default(),
+        );
+        registry.register_builtin("searx", "Searx", searx::Searx::default());
+        registry.register_builtin("brave", "Brave", brave::Brave::default());
+        registry.register_builtin("startpage", "Startpage", startpage::Startpage::default());
+        registry.register_builtin("librex", "LibreX", librex::LibreX::default());
+        registry.register_builtin("mojeek", "Mojeek", mojeek::Mojeek::default());
+        registry.register_builtin("bing", "Bing", bing::Bing::default());
+        registry
     }
 }
 
-impl From<&Engines> for Vec<EngineHandler> {
-    fn from(value: &Engines) -> Self {
-        let mut v = vec![];
-        if value.duckduckgo {
-            let engine = crate::engines::duckduckgo::DuckDuckGo::default();
-            v.push(EngineHandler::new("duckduckgo", Arc::new(engine)));
-        }
-        if value.searx {
-            let engine = crate::engines::searx::Searx::default();
-            v.push(EngineHandler::new("searx", Arc::new(engine)));
-        }
-        if value.brave {
-            let engine = crate::engines::brave::Brave::default();
-            v.push(EngineHandler::new("brave", Arc::new(engine)));
-        }
-        if value.startpage {
-            let engine = crate::engines::startpage::Startpage::default();
-            v.push(EngineHandler::new("startpage", Arc::new(engine)));
-        }
-        if value.librex {
-            let engine = crate::engines::librex::LibreX::default();
-            v.push(EngineHandler::new("librex", Arc::new(engine)));
+impl EngineRegistry {
+    /// Creates a registry containing all the built-in upstream search engines, with the searx and
+    /// librex instances and the engine base urls of the config file, and the declarative html and
+    /// json engines defined in the config file. Declarative engines which fail to load
+    /// (e.g. because of an invalid css selector) are logged and skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - It takes the parsed config struct.
+    pub fn new(config: &Config) -> Self {
+        let mut registry = Self::default();
+
+        // the meta search engines keep their position in the registry when they are replaced.
+        registry.register_builtin("searx", "Searx", searx::Searx::new(&config.searx));
+        registry.register_builtin("librex", "LibreX", librex::LibreX::new(&config.librex));
+
+        for (name, base_url) in &config.engine_base_urls {
+            // the built-in engines keep their lowercase names whatever the case of the key.
+            let name = &name.to_ascii_lowercase();
+            match name.as_str() {
+                "duckduckgo" => registry.register_builtin(
+                    name,
+                    "DuckDuckGo",
+                    duckduckgo::DuckDuckGo::new(base_url),
+                ),
+                "brave" => registry.register_builtin(name, "Brave", brave::Brave::new(base_url)),
+                "startpage" => registry.register_builtin(
+                    name,
+                    "Startpage",
+                    startpage::Startpage::new(base_url),
+                ),
+                "mojeek" => {
+                    registry.register_builtin(name, "Mojeek", mojeek::Mojeek::new(base_url))
+                }
+                "bing" => registry.register_builtin(name, "Bing", bing::Bing::new(base_url)),
+                _ => log::error!("The base url of the engine '{name}' cannot be configured"),
+            }
         }
-        if value.mojeek {
-            let engine = crate::engines::mojeek::Mojeek::default();
-            v.push(EngineHandler::new("mojeek", Arc::new(engine)));
+
+        for (name, engine_config) in &config.html_engines {
+            match html_engine::HtmlEngine::new(name, engine_config) {
+                Ok(engine) => {
+                    let info = engine.info().clone();
+                    registry.register(EngineHandler::new(name, info, Arc::new(engine)));
+                }
+                Err(error) => log::error!("Could not load the engine '{name}': {error:?}"),
+            }
         }
-        if value.bing {
-            let engine = crate::engines::bing::Bing::default();
-            v.push(EngineHandler::new("bing", Arc::new(engine)));
+
+        for (name, engine_config) in &config.json_engines {
+            match json_engine::JsonEngine::new(name, engine_config) {
+                Ok(engine) => {
+                    let info = engine.info().clone();
+                    registry.register(EngineHandler::new(name, info, Arc::new(engine)));
+                }
+                Err(error) => log::error!("Could not load the engine '{name}': {error:?}"),
+            }
         }
-        v
+
+        registry
     }
-}
 
-impl Engines {
-    /// Returns a list of all engines
-    pub fn list(&self) -> Box<[&'static str]> {
-        Box::new([
-            "duckduckgo",
-            "searx",
-            "brave",
-            "startpage",
-            "librex",
-            "mojeek",
-            "bing",
-        ])
+    /// Registers an engine, replacing any engine previously registered under the same name.
+    ///
+    /// # Arguments
+    ///
+    /// * `handler` - It takes the engine handler holding the engine and its metadata.
+    pub fn register(&mut self, handler: EngineHandler) {
+        self.engines.insert(handler.name().to_owned(), handler);
+    }
+
+    /// Registers a built-in engine which provides general results with paging and safe search.
+    fn register_builtin(
+        &mut self,
+        name: &str,
+        display_name: &str,
+        engine: impl SearchEngine + 'static,
+    ) {
+        let info = EngineInfo {
+            display_name: display_name.to_owned(),
+            categories: vec!["general".to_owned()],
+            supports_paging: true,
+            supports_safe_search: true,
+        };
+        self.register(EngineHandler::new(name, info, Arc::new(engine)));
+    }
+
+    /// Returns the engine registered under the given name. The name is matched exactly first and
+    /// then case-insensitively, so that `mywiki` also selects an engine named `MyWiki`.
+    ///
+    /// # Error
+    ///
+    /// Returns `EngineError::NoSuchEngineFound` if no engine is registered under the name.
+    pub fn get(&self, name: &str) -> Result<&EngineHandler, EngineError> {
+        self.engines
+            .get(name)
+            .or_else(|| {
+                self.engines
+                    .values()
+                    .find(|handler| handler.name().eq_ignore_ascii_case(name))
+            })
+            .ok_or_else(|| EngineError::NoSuchEngineFound(name.to_owned()))
+    }
+
+    /// Returns all the registered engines in registration order.
+    pub fn engines(&self) -> impl Iterator<Item = &EngineHandler> {
+        self.engines.values()
+    }
+
+    /// Returns the names of all the registered engines in registration order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.engines.keys().map(String::as_str)
+    }
+
+    /// Looks up the engines selected by the user (or the config file) by their names.
+    ///
+    /// # Arguments
+    ///
+    /// * `names` - It takes the names of the selected engines.
+    ///
+    /// # Returns
+    ///
+    /// It returns the handlers of the known engines together with a `NoSuchEngineFound` error
+    /// info for every unknown engine name. An engine selected under several names (differing in
+    /// case) is only returned once.
+    pub fn select(&self, names: &[String]) -> (Vec<EngineHandler>, Vec<EngineErrorInfo>) {
+        let mut handlers: Vec<EngineHandler> = Vec::with_capacity(names.len());
+        let mut errors = Vec::new();
+        for name in names {
+            match self.get(name) {
+                Ok(handler)
+                    if handlers
+                        .iter()
+                        .any(|selected| selected.name() == handler.name()) => {}
+                Ok(handler) => handlers.push(handler.clone()),
+                Err(error) => {
+                    log::error!("Engine Error: {error}");
+                    errors.push(EngineErrorInfo::new(&error, name));
+                }
+            }
+        }
+        (handlers, errors)
     }
 }
@@ -2,41 +2,92 @@
 //! by querying the upstream mojeek search engine with user provided query and with a page
 //! number if provided.
 
-use std::collections::HashMap;
-
-use reqwest::header::HeaderMap;
 use reqwest::Client;
 use scraper::Html;
 
 use crate::models::aggregation_models::SearchResult;
 
 use crate::models::engine_models::{EngineError, SearchEngine};
+use crate::results::browser_profile::BrowserProfile;
 
-use error_stack::{Report, Result, ResultExt};
+use error_stack::{Report, Result};
 
 use super::search_result_parser::SearchResultParser;
+use super::url_builder::build_url;
+
+/// The base url of Mojeek which is queried unless the config file overrides it.
+pub const DEFAULT_BASE_URL: &str = "https://www.mojeek.com";
 
 /// A new Mojeek engine type defined in-order to implement the `SearchEngine` trait which allows to
 /// reduce code duplication as well as allows to create vector of different search engines easily.
 pub struct Mojeek {
     /// The parser, used to interpret the search result.
     parser: SearchResultParser,
+    /// The base url of the engine without a trailing slash.
+    base_url: String,
 }
 
 impl Default for Mojeek {
     /// Creates the Mojeek parser.
     fn default() -> Self {
+        Self::new(DEFAULT_BASE_URL)
+    }
+}
+
+impl Mojeek {
+    /// Creates the Mojeek engine querying the given base url.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - It takes the base url of the engine (e.g. `https://www.mojeek.com`).
+    pub fn new(base_url: &str) -> Self {
         Self {
             parser: SearchResultParser::new(
                 ".result-col",
                 ".results-standard li",
-                "a span.url",
+                "h2 a.title",
                 "h2 a.title",
                 "p.s",
             )
             .expect("somehow you changed the static stings in the binary i guess"),
+            base_url: base_url.trim_end_matches('/').to_owned(),
         }
     }
+
+    /// Parses the html of a result page into the search results.
+    ///
+    /// # Arguments
+    ///
+    /// * `html` - It takes the html of the result page.
+    ///
+    /// # Error
+    ///
+    /// Returns an `EmptyResultSet` error if the page reports that there are no results.
+    pub fn parse_results(&self, html: &str) -> Result<Vec<(String, SearchResult)>, EngineError> {
+        let document: Html = Html::parse_document(html);
+
+        if let Some(no_result_msg) = self.parser.parse_for_no_results(&document).nth(0) {
+            if no_result_msg
+                .inner_html()
+                .contains("No pages found matching:")
+            {
+                return Err(Report::new(EngineError::EmptyResultSet));
+            }
+        }
+
+        // scrape all the results from the html
+        self.parser
+            .parse_for_results(&document, |title, url, desc| {
+                url.value().attr("href").map(|url| {
+                    SearchResult::new(
+                        title.inner_html().trim(),
+                        url.trim(),
+                        desc.inner_html().trim(),
+                        &["mojeek"],
+                    )
+                })
+            })
+    }
 }
 
 #[async_trait::async_trait]
@@ -45,8 +96,9 @@ impl SearchEngine for Mojeek {
         &self,
         query: &str,
         page: u32,
-        user_agent: &str,
+        profile: &BrowserProfile,
         client: &Client,
+        safe_search: u8,
     ) -> Result<Vec<(String, SearchResult)>, EngineError> {
         // Mojeek uses `start results from this number` convention
         // So, for 10 results per page, page 0 starts at 1, page 1
@@ -73,7 +125,13 @@ impl SearchEngine for Mojeek {
             "You",
         ];
 
-        let qss = search_engines.join("%2C");
+        let qss = search_engines.join(",");
+
+        // Mojeek only supports turning safe search on or off.
+        let safe = match safe_search {
+            0 => "0",
+            _ => "1",
+        };
 
         // Mojeek detects automated requests, these are preferences that are
         // able to circumvent the countermeasure. Some of these are
@@ -89,62 +147,27 @@ impl SearchEngine for Mojeek {
             ("hp", "minimal"),
             ("lb", "en"),
             ("qss", &qss),
+            ("safe", safe),
         ];
 
-        let mut query_params_string = String::new();
-        for (k, v) in &query_params {
-            query_params_string.push_str(&format!("&{k}={v}"));
+        let mut url_params = vec![("q", query)];
+        if page != 0 {
+            url_params.push(("s", &start_result));
         }
-
-        let url: String = match page {
-            0 => {
-                format!("https://www.mojeek.com/search?q={query}{query_params_string}")
-            }
-            _ => {
-                format!(
-                    "https://www.mojeek.com/search?q={query}&s={start_result}{query_params_string}"
-                )
-            }
-        };
+        url_params.extend_from_slice(&query_params);
+        let url: String = build_url(&format!("{}/search", self.base_url), &url_params)?;
 
         let mut cookie_string = String::new();
         for (k, v) in &query_params {
-            cookie_string.push_str(&format!("{k}={v}; "));
+            cookie_string.push_str(&format!("{k}={}; ", v.replace(',', "%2C")));
         }
 
-        let header_map = HeaderMap::try_from(&HashMap::from([
-            ("User-Agent".to_string(), user_agent.to_string()),
-            ("Referer".to_string(), "https://google.com/".to_string()),
-            (
-                "Content-Type".to_string(),
-                "application/x-www-form-urlencoded".to_string(),
-            ),
-            ("Cookie".to_string(), cookie_string),
-        ]))
-        .change_context(EngineError::UnexpectedError)?;
-
-        let document: Html = Html::parse_document(
-            &Mojeek::fetch_html_from_upstream(self, &url, header_map, client).await?,
-        );
+        let header_map = profile.header_map(&[
+            ("Content-Type", "application/x-www-form-urlencoded"),
+            ("Cookie", &cookie_string),
+        ])?;
 
-        if let Some(no_result_msg) = self.parser.parse_for_no_results(&document).nth(0) {
-            if no_result_msg
-                .inner_html()
-                .contains("No pages found matching:")
-            {
-                return Err(Report::new(EngineError::EmptyResultSet));
-            }
-        }
-
-        // scrape all the results from the html
-        self.parser
-            .parse_for_results(&document, |title, url, desc| {
-                Some(SearchResult::new(
-                    title.inner_html().trim(),
-                    url.inner_html().trim(),
-                    desc.inner_html().trim(),
-                    &["mojeek"],
-                ))
-            })
+        let html = Mojeek::fetch_html_from_upstream(self, &url, header_map, client).await?;
+        self.parse_results(&html)
     }
 }
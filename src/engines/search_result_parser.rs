@@ -1,8 +1,13 @@
 //! This modules provides helper functionalities for parsing a html document into internal SearchResult.
 
-use crate::models::{aggregation_models::SearchResult, engine_models::EngineError};
+use crate::models::{
+    aggregation_models::SearchResult,
+    engine_models::{is_captcha, EngineError},
+};
 use error_stack::{Report, Result};
+use reqwest::Url;
 use scraper::{html::Select, ElementRef, Html, Selector};
+use std::fmt::Write;
 
 /// A html search result parser, based on a predefined CSS selectors.
 pub struct SearchResultParser {
@@ -41,13 +46,16 @@ impl SearchResultParser {
         document.select(&self.no_result)
     }
 
-    /// Parse the html, and convert the results to SearchResult with the help of the builder function
+    /// Parse the html, and convert the results to SearchResult with the help of the builder function.
+    /// As the engines check for an empty result page before, a page from which no result could be
+    /// parsed is reported as a `Captcha` error if it is a captcha page and as a `ParseFailure`
+    /// otherwise (e.g. because the engine changed its markup).
     pub fn parse_for_results(
         &self,
         document: &Html,
         builder: impl Fn(&ElementRef<'_>, &ElementRef<'_>, &ElementRef<'_>) -> Option<SearchResult>,
     ) -> Result<Vec<(String, SearchResult)>, EngineError> {
-        let res = document
+        let res: Vec<(String, SearchResult)> = document
             .select(&self.results)
             .filter_map(|result| {
                 let title = result.select(&self.result_title).next();
@@ -60,6 +68,15 @@ impl SearchResultParser {
             })
             .map(|search_result| (search_result.url.clone(), search_result))
             .collect();
+
+        if res.is_empty() {
+            let html = document.html();
+            return Err(match is_captcha(&html) {
+                true => Report::new(EngineError::Captcha),
+                false => Report::new(EngineError::ParseFailure)
+                    .attach_printable("no search result could be parsed from the page"),
+            });
+        }
         Ok(res)
     }
 }
@@ -73,3 +90,19 @@ fn new_selector(selector: &str) -> Result<Selector, EngineError> {
         ))
     })
 }
+
+/// Escapes the plain text of a search result (like a title extracted with `ElementRef::text`),
+/// as the titles and descriptions of the results are rendered as html in the search page.
+pub fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    // writing to a string cannot fail.
+    let _ = maud::Escaper::new(&mut escaped).write_str(text);
+    escaped
+}
+
+/// Resolves the link of a search result against the url of the result page. Only http and https
+/// urls are returned, so that `javascript:` or `data:` links never reach the search page.
+pub fn result_url(base_url: &Url, href: &str) -> Option<Url> {
+    let url = base_url.join(href.trim()).ok()?;
+    matches!(url.scheme(), "http" | "https").then_some(url)
+}
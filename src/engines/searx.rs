@@ -1,27 +1,73 @@
-//! The `searx` module handles the scraping of results from the searx search engine instance
-//! by querying the upstream searx search engine instance with user provided query and with a page
-//! number if provided.
+//! The `searx` module handles the scraping of results from the searx search engine instances
+//! by querying the upstream searx search engine instances with user provided query and with a page
+//! number if provided. Self-hosted SearXNG instances can also be queried through their json api.
 
-use reqwest::header::HeaderMap;
 use reqwest::Client;
 use scraper::Html;
-use std::collections::HashMap;
+use serde::Deserialize;
 
-use super::search_result_parser::SearchResultParser;
+use super::instance_pool::InstancePool;
+use super::search_result_parser::{escape_text, SearchResultParser};
+use super::url_builder::build_url;
+use crate::config::{InstancePoolConfig, InstanceSelection, SearxConfig, SearxFormat};
 use crate::models::aggregation_models::SearchResult;
 use crate::models::engine_models::{EngineError, SearchEngine};
+use crate::results::browser_profile::BrowserProfile;
 use error_stack::{Report, Result, ResultExt};
 
+/// The searx instance which is queried if no instances are configured.
+pub const DEFAULT_INSTANCE: &str = "https://searx.be";
+
 /// A new Searx engine type defined in-order to implement the `SearchEngine` trait which allows to
 /// reduce code duplication as well as allows to create vector of different search engines easily.
 pub struct Searx {
     /// The parser, used to interpret the search result.
     parser: SearchResultParser,
+    /// The instances which are queried.
+    pool: InstancePool,
+    /// The format in which the results are requested.
+    format: SearxFormat,
+}
+
+/// A named struct which deserializes the json response of a SearXNG instance.
+#[derive(Deserialize)]
+struct SearxResponse {
+    /// The search results of the response.
+    results: Vec<SearxResult>,
+}
+
+/// A named struct which deserializes a search result of the json response of a SearXNG instance.
+#[derive(Deserialize)]
+struct SearxResult {
+    /// The title of the search result.
+    title: String,
+    /// The url of the search result.
+    url: String,
+    /// The description of the search result, which is missing or null for some results.
+    #[serde(default)]
+    content: Option<String>,
 }
 
 impl Default for Searx {
-    /// creates a Searx parser
+    /// creates a Searx parser querying the default instance
     fn default() -> Self {
+        Self::new(&SearxConfig {
+            pool: InstancePoolConfig {
+                instances: vec![DEFAULT_INSTANCE.to_owned()],
+                selection: InstanceSelection::default(),
+            },
+            format: SearxFormat::default(),
+        })
+    }
+}
+
+impl Searx {
+    /// Creates a Searx engine querying the configured instances.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - It takes the searx config with the instances and the response format.
+    pub fn new(config: &SearxConfig) -> Self {
         Self {
             parser: SearchResultParser::new(
                 "#urls>.dialog-error>p",
@@ -31,40 +77,58 @@ impl Default for Searx {
                 ".content",
             )
             .expect("somehow you changed the static stings in the binary i guess"),
+            pool: InstancePool::new(&config.pool),
+            format: config.format,
         }
     }
-}
 
-#[async_trait::async_trait]
-impl SearchEngine for Searx {
-    async fn results(
+    /// Scrapes the results from the html result page of an instance.
+    async fn html_results(
         &self,
+        instance: &str,
         query: &str,
         page: u32,
-        user_agent: &str,
+        profile: &BrowserProfile,
         client: &Client,
+        safe_search_level: u8,
     ) -> Result<Vec<(String, SearchResult)>, EngineError> {
-        let url: String = format!("https://searx.be/search?q={query}&pageno={}", page + 1);
+        let url: String = build_url(
+            &format!("{instance}/search"),
+            &[("q", query), ("pageno", &(page + 1).to_string())],
+        )?;
 
-        // initializing headers and adding appropriate headers.
-        let header_map = HeaderMap::try_from(&HashMap::from([
-            ("User-Agent".to_string(), user_agent.to_string()),
-            ("Referer".to_string(), "https://google.com/".to_string()),
-            ("Content-Type".to_string(), "application/x-www-form-urlencoded".to_string()),
-            ("Cookie".to_string(), "categories=general; language=auto; locale=en; autocomplete=duckduckgo; image_proxy=1; method=POST; safesearch=2; theme=simple; results_on_new_tab=1; doi_resolver=oadoi.org; simple_style=auto; center_alignment=1; query_in_title=1; infinite_scroll=0; disabled_engines=; enabled_engines=\"archive is__general\\054yep__general\\054curlie__general\\054currency__general\\054ddg definitions__general\\054wikidata__general\\054duckduckgo__general\\054tineye__general\\054lingva__general\\054startpage__general\\054yahoo__general\\054wiby__general\\054marginalia__general\\054alexandria__general\\054wikibooks__general\\054wikiquote__general\\054wikisource__general\\054wikiversity__general\\054wikivoyage__general\\054dictzone__general\\054seznam__general\\054mojeek__general\\054naver__general\\054wikimini__general\\054brave__general\\054petalsearch__general\\054goo__general\"; disabled_plugins=; enabled_plugins=\"searx.plugins.hostname_replace\\054searx.plugins.oa_doi_rewrite\\054searx.plugins.vim_hotkeys\"; tokens=; maintab=on; enginetab=on".to_string())
-        ]))
-        .change_context(EngineError::UnexpectedError)?;
+        // adding the engine specific headers to the headers of the browser profile.
+        let header_map = profile.header_map(&[
+            ("Content-Type", "application/x-www-form-urlencoded"),
+            ("Cookie", &format!("categories=general; language=auto; locale=en; autocomplete=duckduckgo; image_proxy=1; method=POST; safesearch={safe_search_level}; theme=simple; results_on_new_tab=1; doi_resolver=oadoi.org; simple_style=auto; center_alignment=1; query_in_title=1; infinite_scroll=0; disabled_engines=; enabled_engines=\"archive is__general\\054yep__general\\054curlie__general\\054currency__general\\054ddg definitions__general\\054wikidata__general\\054duckduckgo__general\\054tineye__general\\054lingva__general\\054startpage__general\\054yahoo__general\\054wiby__general\\054marginalia__general\\054alexandria__general\\054wikibooks__general\\054wikiquote__general\\054wikisource__general\\054wikiversity__general\\054wikivoyage__general\\054dictzone__general\\054seznam__general\\054mojeek__general\\054naver__general\\054wikimini__general\\054brave__general\\054petalsearch__general\\054goo__general\"; disabled_plugins=; enabled_plugins=\"searx.plugins.hostname_replace\\054searx.plugins.oa_doi_rewrite\\054searx.plugins.vim_hotkeys\"; tokens=; maintab=on; enginetab=on")),
+        ])?;
 
-        let document: Html = Html::parse_document(
-            &Searx::fetch_html_from_upstream(self, &url, header_map, client).await?,
-        );
+        let html = Searx::fetch_html_from_upstream(self, &url, header_map, client).await?;
+        self.parse_html_results(&html)
+    }
+
+    /// Parses the html of a result page into the search results.
+    ///
+    /// # Arguments
+    ///
+    /// * `html` - It takes the html of the result page.
+    ///
+    /// # Error
+    ///
+    /// Returns an `EmptyResultSet` error if the page reports that there are no results.
+    pub fn parse_html_results(
+        &self,
+        html: &str,
+    ) -> Result<Vec<(String, SearchResult)>, EngineError> {
+        let document: Html = Html::parse_document(html);
 
         if let Some(no_result_msg) = self.parser.parse_for_no_results(&document).nth(1) {
-            if no_result_msg.inner_html()
-            == "we didn't find any results. Please use another query or search in more categories"
-        {
-            return Err(Report::new(EngineError::EmptyResultSet));
-        }
+            if no_result_msg
+                .inner_html()
+                .contains("we didn't find any results")
+            {
+                return Err(Report::new(EngineError::EmptyResultSet));
+            }
         }
 
         // scrape all the results from the html
@@ -80,4 +144,97 @@ impl SearchEngine for Searx {
                 })
             })
     }
+
+    /// Fetches the results from the json api of a SearXNG instance.
+    async fn json_results(
+        &self,
+        instance: &str,
+        query: &str,
+        page: u32,
+        profile: &BrowserProfile,
+        client: &Client,
+        safe_search_level: u8,
+    ) -> Result<Vec<(String, SearchResult)>, EngineError> {
+        let url: String = build_url(
+            &format!("{instance}/search"),
+            &[
+                ("q", query),
+                ("pageno", &(page + 1).to_string()),
+                ("safesearch", &safe_search_level.to_string()),
+                ("format", "json"),
+            ],
+        )?;
+
+        let header_map = profile.header_map(&[("Accept", "application/json")])?;
+
+        let body = Searx::fetch_json_as_bytes_from_upstream(self, &url, header_map, client).await?;
+        self.parse_json_results(&body)
+    }
+
+    /// Parses the json response of the search api into the search results.
+    ///
+    /// # Arguments
+    ///
+    /// * `body` - It takes the body of the json response.
+    ///
+    /// # Error
+    ///
+    /// Returns an `EmptyResultSet` error if the response contains no results and a
+    /// `ParseFailure` if the response is not a valid search response.
+    pub fn parse_json_results(
+        &self,
+        body: &[u8],
+    ) -> Result<Vec<(String, SearchResult)>, EngineError> {
+        let response: SearxResponse =
+            serde_json::from_slice(body).change_context(EngineError::ParseFailure)?;
+
+        if response.results.is_empty() {
+            return Err(Report::new(EngineError::EmptyResultSet));
+        }
+
+        Ok(response
+            .results
+            .into_iter()
+            .map(|result| {
+                // the text is escaped as the html results are, since both are rendered as html.
+                let search_result = SearchResult::new(
+                    &escape_text(result.title.trim()),
+                    result.url.trim(),
+                    &escape_text(result.content.as_deref().unwrap_or_default().trim()),
+                    &["searx"],
+                );
+                (search_result.url.clone(), search_result)
+            })
+            .collect())
+    }
+}
+
+#[async_trait::async_trait]
+impl SearchEngine for Searx {
+    async fn results(
+        &self,
+        query: &str,
+        page: u32,
+        profile: &BrowserProfile,
+        client: &Client,
+        safe_search: u8,
+    ) -> Result<Vec<(String, SearchResult)>, EngineError> {
+        // Searx uses the same levels (0 = off, 1 = moderate, 2 = strict).
+        let safe_search_level = safe_search.min(2);
+
+        self.pool
+            .failover("searx", |instance| async move {
+                match self.format {
+                    SearxFormat::Html => {
+                        self.html_results(instance, query, page, profile, client, safe_search_level)
+                            .await
+                    }
+                    SearxFormat::Json => {
+                        self.json_results(instance, query, page, profile, client, safe_search_level)
+                            .await
+                    }
+                }
+            })
+            .await
+    }
 }
@@ -2,30 +2,46 @@
 //! by querying the upstream startpage search engine with user provided query and with a page
 //! number if provided.
 
-use std::collections::HashMap;
-
-use reqwest::header::HeaderMap;
 use reqwest::Client;
 use scraper::Html;
 
 use crate::models::aggregation_models::SearchResult;
 
 use crate::models::engine_models::{EngineError, SearchEngine};
+use crate::results::browser_profile::BrowserProfile;
 
-use error_stack::{Report, Result, ResultExt};
+use error_stack::{Report, Result};
 
 use super::search_result_parser::SearchResultParser;
+use super::suggestion_parser::parse_opensearch_suggestions;
+use super::url_builder::build_url;
+
+/// The base url of Startpage which is queried unless the config file overrides it.
+pub const DEFAULT_BASE_URL: &str = "https://www.startpage.com";
 
 /// A new Startpage engine type defined in-order to implement the `SearchEngine` trait which allows to
 /// reduce code duplication as well as allows to create vector of different search engines easily.
 pub struct Startpage {
     /// The parser, used to interpret the search result.
     parser: SearchResultParser,
+    /// The base url of the engine without a trailing slash.
+    base_url: String,
 }
 
 impl Default for Startpage {
     /// Creates the Startpage parser.
     fn default() -> Self {
+        Self::new(DEFAULT_BASE_URL)
+    }
+}
+
+impl Startpage {
+    /// Creates the Startpage engine querying the given base url.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - It takes the base url of the engine (e.g. `https://www.startpage.com`).
+    pub fn new(base_url: &str) -> Self {
         Self {
             parser: SearchResultParser::new(
                 ".no-results",
@@ -35,41 +51,21 @@ impl Default for Startpage {
                 ".w-gl__description",
             )
             .expect("somehow you changed the static stings in the binary i guess"),
+            base_url: base_url.trim_end_matches('/').to_owned(),
         }
     }
-}
 
-#[async_trait::async_trait]
-impl SearchEngine for Startpage {
-    async fn results(
-        &self,
-        query: &str,
-        page: u32,
-        user_agent: &str,
-        client: &Client,
-    ) -> Result<Vec<(String, SearchResult)>, EngineError> {
-        // Page number can be missing or empty string and so appropriate handling is required
-        // so that upstream server recieves valid page number.
-        let url: String = format!(
-            "https://startpage.com/do/dsearch?q={query}&num=10&start={}",
-            page * 10,
-        );
-
-        // initializing HeaderMap and adding appropriate headers.
-        let header_map = HeaderMap::try_from(&HashMap::from([
-            ("User-Agent".to_string(), user_agent.to_string()),
-            ("Referer".to_string(), "https://google.com/".to_string()),
-            (
-                "Content-Type".to_string(),
-                "application/x-www-form-urlencoded".to_string(),
-            ),
-            ("Cookie".to_string(), "preferences=connect_to_serverEEE0N1Ndate_timeEEEworldN1Ndisable_family_filterEEE0N1Ndisable_open_in_new_windowEEE0N1Nenable_post_methodEEE1N1Nenable_proxy_safety_suggestEEE1N1Nenable_stay_controlEEE0N1Ninstant_answersEEE1N1Nlang_homepageEEEs%2Fnight%2FenN1NlanguageEEEenglishN1Nlanguage_uiEEEenglishN1Nnum_of_resultsEEE10N1Nsearch_results_regionEEEallN1NsuggestionsEEE1N1Nwt_unitEEEcelsius".to_string()),
-        ]))
-        .change_context(EngineError::UnexpectedError)?;
-
-        let document: Html = Html::parse_document(
-            &Startpage::fetch_html_from_upstream(self, &url, header_map, client).await?,
-        );
+    /// Parses the html of a result page into the search results.
+    ///
+    /// # Arguments
+    ///
+    /// * `html` - It takes the html of the result page.
+    ///
+    /// # Error
+    ///
+    /// Returns an `EmptyResultSet` error if the page reports that there are no results.
+    pub fn parse_results(&self, html: &str) -> Result<Vec<(String, SearchResult)>, EngineError> {
+        let document: Html = Html::parse_document(html);
 
         if self.parser.parse_for_no_results(&document).next().is_some() {
             return Err(Report::new(EngineError::EmptyResultSet));
@@ -87,3 +83,64 @@ impl SearchEngine for Startpage {
             })
     }
 }
+
+#[async_trait::async_trait]
+impl SearchEngine for Startpage {
+    async fn results(
+        &self,
+        query: &str,
+        page: u32,
+        profile: &BrowserProfile,
+        client: &Client,
+        safe_search: u8,
+    ) -> Result<Vec<(String, SearchResult)>, EngineError> {
+        // Page number can be missing or empty string and so appropriate handling is required
+        // so that upstream server recieves valid page number.
+        let url: String = build_url(
+            &format!("{}/do/dsearch", self.base_url),
+            &[
+                ("q", query),
+                ("num", "10"),
+                ("start", &(page * 10).to_string()),
+            ],
+        )?;
+
+        // Startpage only supports turning the family filter on or off.
+        let disable_family_filter = match safe_search {
+            0 => 1,
+            _ => 0,
+        };
+
+        // adding the engine specific headers to the headers of the browser profile.
+        let header_map = profile.header_map(&[
+            ("Content-Type", "application/x-www-form-urlencoded"),
+            ("Cookie", &format!("preferences=connect_to_serverEEE0N1Ndate_timeEEEworldN1Ndisable_family_filterEEE{disable_family_filter}N1Ndisable_open_in_new_windowEEE0N1Nenable_post_methodEEE1N1Nenable_proxy_safety_suggestEEE1N1Nenable_stay_controlEEE0N1Ninstant_answersEEE1N1Nlang_homepageEEEs%2Fnight%2FenN1NlanguageEEEenglishN1Nlanguage_uiEEEenglishN1Nnum_of_resultsEEE10N1Nsearch_results_regionEEEallN1NsuggestionsEEE1N1Nwt_unitEEEcelsius")),
+        ])?;
+
+        let html = Startpage::fetch_html_from_upstream(self, &url, header_map, client).await?;
+        self.parse_results(&html)
+    }
+
+    async fn suggestions(
+        &self,
+        query: &str,
+        profile: &BrowserProfile,
+        client: &Client,
+    ) -> Result<Vec<String>, EngineError> {
+        let url = build_url(
+            &format!("{}/suggestions", self.base_url),
+            &[
+                ("q", query),
+                ("format", "opensearch"),
+                ("segment", "startpage.udog"),
+            ],
+        )?;
+
+        let header_map = profile.header_map(&[])?;
+
+        parse_opensearch_suggestions(
+            &Startpage::fetch_json_as_bytes_from_upstream(self, url.as_str(), header_map, client)
+                .await?,
+        )
+    }
+}
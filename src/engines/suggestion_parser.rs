@@ -0,0 +1,29 @@
+//! This modules provides helper functionalities for parsing the autocomplete responses of the
+//! upstream search engines into a list of suggestions.
+
+use crate::models::engine_models::EngineError;
+use error_stack::{Report, Result, ResultExt};
+
+/// Parses a json response in the opensearch suggestions format, e.g.
+/// `["rust", ["rust lang", "rustup"]]`, into the list of suggestions it contains. Some engines
+/// append extra arrays (descriptions, urls) which are ignored.
+///
+/// # Arguments
+///
+/// * `bytes` - It takes the raw json response body of the upstream autocomplete api.
+///
+/// # Error
+///
+/// It returns an `EngineError::UnexpectedError` if the response is not in the opensearch
+/// suggestions format.
+pub fn parse_opensearch_suggestions(bytes: &[u8]) -> Result<Vec<String>, EngineError> {
+    let response: Vec<serde_json::Value> =
+        serde_json::from_slice(bytes).change_context(EngineError::UnexpectedError)?;
+
+    let suggestions = response.into_iter().nth(1).ok_or_else(|| {
+        Report::new(EngineError::UnexpectedError)
+            .attach_printable("suggestions response does not contain a list of suggestions")
+    })?;
+
+    serde_json::from_value(suggestions).change_context(EngineError::UnexpectedError)
+}
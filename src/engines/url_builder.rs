@@ -0,0 +1,38 @@
+//! This module provides the helpers to build the urls of the requests to the upstream search
+//! engines, which encode the user provided query so that reserved characters (like `&`, `#` and
+//! `+`) and non ascii characters reach the upstream engine unchanged.
+
+use error_stack::{Result, ResultExt};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use reqwest::Url;
+
+use crate::models::engine_models::EngineError;
+
+/// Builds the url of a request to an upstream search engine by appending the form encoded query
+/// parameters to the base url.
+///
+/// # Arguments
+///
+/// * `base_url` - It takes the url of the search page of the upstream engine without the query
+///   string.
+/// * `params` - It takes the query parameters (including the user provided query) as key value
+///   pairs, which are encoded in the given order.
+///
+/// # Error
+///
+/// Returns an `EngineError` if the base url is not a valid url.
+pub fn build_url(base_url: &str, params: &[(&str, &str)]) -> Result<String, EngineError> {
+    Url::parse_with_params(base_url, params)
+        .map(String::from)
+        .change_context(EngineError::UnexpectedError)
+        .attach_printable_lazy(|| format!("invalid engine url: {base_url}"))
+}
+
+/// Percent encodes a query so that it can be spliced into a url template.
+///
+/// # Arguments
+///
+/// * `query` - It takes the user provided query.
+pub fn encode_query(query: &str) -> String {
+    utf8_percent_encode(query, NON_ALPHANUMERIC).to_string()
+}
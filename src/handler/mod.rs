@@ -12,7 +12,7 @@ const PUBLIC_DIRECTORY_NAME: &str = "public";
 /// The constant holding the name of the common folder.
 const COMMON_DIRECTORY_NAME: &str = "crabbysearch";
 /// The constant holding the name of the config file.
-const CONFIG_FILE_NAME: &str = "config.lua";
+const CONFIG_FILE_NAME: &str = "config.toml";
 /// The constant holding the name of the AllowList text file.
 const ALLOWLIST_FILE_NAME: &str = "allowlist.txt";
 /// The constant holding the name of the BlockList text file.
@@ -0,0 +1,240 @@
+//! This module provides the health state of the upstream search engines, which is shared across
+//! the requests. It skips the engines whose requests keep failing (a circuit breaker) and keeps
+//! the statistics served by the health route and shown on the status page.
+
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+use crate::{
+    config::Config,
+    models::{
+        engine_models::EngineError,
+        health_models::{EngineState, EngineStatus, HealthReport},
+    },
+};
+
+/// The maximum number of requests per engine which are kept to compute the statistics.
+const MAX_SAMPLES: usize = 1000;
+
+/// A named struct which stores the outcome of a request to an upstream engine.
+struct Sample {
+    /// The time at which the request finished.
+    at: Instant,
+    /// The time the engine took to answer.
+    latency: Duration,
+    /// Whether the request failed.
+    failed: bool,
+}
+
+/// A named struct which stores the health state of an upstream engine.
+#[derive(Default)]
+struct EngineHealthState {
+    /// The number of requests to the engine which failed in a row.
+    consecutive_failures: u32,
+    /// The time until which the engine is skipped, if its requests failed too often.
+    disabled_until: Option<Instant>,
+    /// The outcomes of the latest requests to the engine, the oldest first.
+    samples: VecDeque<Sample>,
+    /// The time of the last successful request.
+    last_success: Option<OffsetDateTime>,
+    /// The kind of error and the time of the last failed request.
+    last_error: Option<(&'static str, OffsetDateTime)>,
+}
+
+impl EngineHealthState {
+    /// Drops the samples which are older than the window.
+    fn prune(&mut self, window: Duration) {
+        while self
+            .samples
+            .front()
+            .is_some_and(|sample| sample.at.elapsed() > window)
+        {
+            self.samples.pop_front();
+        }
+    }
+}
+
+/// Shared health state of the upstream search engines.
+#[derive(Clone)]
+pub struct EngineHealth {
+    /// The health state of the engines, keyed by their engine name.
+    states: Arc<Mutex<HashMap<String, EngineHealthState>>>,
+    /// The number of consecutive failures after which an engine is skipped.
+    failure_threshold: u32,
+    /// The time for which a failing engine is skipped.
+    cool_down: Duration,
+    /// The time over which the statistics of the engines are computed.
+    window: Duration,
+    /// The time at which the health state was built, used as the start time of the server.
+    started: Instant,
+}
+
+impl EngineHealth {
+    /// Builds the health state in which every engine is enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - It takes the parsed config struct.
+    pub fn build(config: &Config) -> Self {
+        Self {
+            states: Arc::default(),
+            failure_threshold: config.circuit_breaker.failure_threshold,
+            cool_down: Duration::from_secs(config.circuit_breaker.cool_down),
+            window: Duration::from_secs(config.health_window),
+            started: Instant::now(),
+        }
+    }
+
+    /// Returns whether a request may be sent to the engine. Once the cool-down period of a
+    /// skipped engine has passed, a single request is allowed to probe the engine and the engine
+    /// is skipped for another cool-down period until the outcome of the probe is recorded.
+    ///
+    /// # Arguments
+    ///
+    /// * `engine` - It takes the name of the engine.
+    pub fn allow(&self, engine: &str) -> bool {
+        let mut states = self.states.lock().unwrap_or_else(PoisonError::into_inner);
+        let Some(state) = states.get_mut(engine) else {
+            return true;
+        };
+        match state.disabled_until {
+            Some(disabled_until) if Instant::now() < disabled_until => false,
+            Some(_) => {
+                state.disabled_until = Some(Instant::now() + self.cool_down);
+                true
+            }
+            None => true,
+        }
+    }
+
+    /// Returns whether the engine is currently skipped, without probing it.
+    ///
+    /// # Arguments
+    ///
+    /// * `engine` - It takes the name of the engine.
+    pub fn is_disabled(&self, engine: &str) -> bool {
+        let states = self.states.lock().unwrap_or_else(PoisonError::into_inner);
+        states
+            .get(engine)
+            .and_then(|state| state.disabled_until)
+            .is_some_and(|disabled_until| Instant::now() < disabled_until)
+    }
+
+    /// Records the outcome of a request to the engine. An empty result set is a successful
+    /// request, the engine is skipped once the failure threshold is reached.
+    ///
+    /// # Arguments
+    ///
+    /// * `engine` - It takes the name of the engine.
+    /// * `latency` - It takes the time the engine took to answer.
+    /// * `error` - It takes the error the request failed with, or `None` if it succeeded.
+    pub fn record(&self, engine: &str, latency: Duration, error: Option<&EngineError>) {
+        let mut states = self.states.lock().unwrap_or_else(PoisonError::into_inner);
+        let state = states.entry(engine.to_owned()).or_default();
+        let failed = !matches!(error, None | Some(EngineError::EmptyResultSet));
+
+        state.prune(self.window);
+        if state.samples.len() >= MAX_SAMPLES {
+            state.samples.pop_front();
+        }
+        state.samples.push_back(Sample {
+            at: Instant::now(),
+            latency,
+            failed,
+        });
+
+        match error {
+            Some(error) if failed => {
+                state.last_error = Some((error.kind(), OffsetDateTime::now_utc()));
+                state.consecutive_failures = state.consecutive_failures.saturating_add(1);
+                if self.failure_threshold > 0
+                    && state.consecutive_failures >= self.failure_threshold
+                {
+                    if state.consecutive_failures == self.failure_threshold {
+                        log::warn!(
+                            "Engine {engine} is skipped for {} secs after {} failures",
+                            self.cool_down.as_secs(),
+                            state.consecutive_failures
+                        );
+                    }
+                    state.disabled_until = Some(Instant::now() + self.cool_down);
+                }
+            }
+            _ => {
+                state.last_success = Some(OffsetDateTime::now_utc());
+                state.consecutive_failures = 0;
+                state.disabled_until = None;
+            }
+        }
+    }
+
+    /// Returns the health of the server and of the given upstream engines, with the error rate
+    /// and the latencies computed over the health window.
+    ///
+    /// # Arguments
+    ///
+    /// * `engines` - It takes the names of the engines to report on.
+    pub fn report<'a>(&self, engines: impl Iterator<Item = &'a str>) -> HealthReport {
+        let mut states = self.states.lock().unwrap_or_else(PoisonError::into_inner);
+        let now = Instant::now();
+        let engines: BTreeMap<String, EngineStatus> = engines
+            .map(|engine| {
+                let status = match states.get_mut(engine) {
+                    Some(state) => {
+                        state.prune(self.window);
+                        self.status(state, now)
+                    }
+                    None => self.status(&EngineHealthState::default(), now),
+                };
+                (engine.to_owned(), status)
+            })
+            .collect();
+
+        HealthReport {
+            status: "ok".to_owned(),
+            uptime: self.started.elapsed().as_secs(),
+            window: self.window.as_secs(),
+            engines,
+        }
+    }
+
+    /// Computes the status of an engine from its health state.
+    fn status(&self, state: &EngineHealthState, now: Instant) -> EngineStatus {
+        let format = |at: &OffsetDateTime| at.format(&Rfc3339).ok();
+        let requests = state.samples.len();
+        let failures = state.samples.iter().filter(|sample| sample.failed).count();
+
+        let mut latencies: Vec<u64> = state
+            .samples
+            .iter()
+            .map(|sample| sample.latency.as_millis() as u64)
+            .collect();
+        latencies.sort_unstable();
+        // the nearest-rank percentile of the latencies.
+        let percentile = |percentile: usize| {
+            let rank = (percentile * latencies.len()).div_ceil(100);
+            latencies.get(rank.saturating_sub(1)).copied()
+        };
+
+        EngineStatus {
+            state: match (state.disabled_until, &state.last_error) {
+                (Some(disabled_until), _) if now < disabled_until => EngineState::Disabled,
+                _ if state.consecutive_failures > 0 => EngineState::Failing,
+                (_, None) if state.last_success.is_none() => EngineState::Unknown,
+                _ => EngineState::Healthy,
+            },
+            last_success: state.last_success.as_ref().and_then(format),
+            last_error: state.last_error.map(|(kind, _)| kind.to_owned()),
+            last_error_at: state.last_error.as_ref().and_then(|(_, at)| format(at)),
+            requests,
+            error_rate: (requests > 0).then(|| failures as f64 / requests as f64),
+            p50_latency: percentile(50),
+            p95_latency: percentile(95),
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! This main library module provides the functionality to provide and handle the Tcp server
+//! and register all the routes for the `crabbysearch` meta search engine website.
+
+#![forbid(unsafe_code, clippy::panic)]
+#![deny(missing_docs, clippy::perf)]
+#![warn(clippy::cognitive_complexity, rust_2018_idioms)]
+
+pub mod cache;
+pub mod config;
+pub mod engines;
+pub mod handler;
+pub mod health;
+pub mod metrics;
+pub mod models;
+pub mod results;
+pub mod server;
+pub mod templates;
+
+use std::net::TcpListener;
+
+use crate::cache::Cache;
+use crate::health::EngineHealth;
+use crate::metrics::Metrics;
+use crate::server::router;
+
+use actix_cors::Cors;
+use actix_files as fs;
+use actix_web::{
+    dev::Server,
+    http::header,
+    middleware::{from_fn, Compress, Logger},
+    web, App, HttpServer,
+};
+use config::Config;
+use engines::EngineRegistry;
+use handler::{file_path, FileType};
+
+/// Runs the web server on the provided TCP listener and returns a `Server` future which has to be
+/// awaited to serve requests.
+///
+/// # Arguments
+///
+/// * `listener` - It takes a tcp listener on which the server should listen.
+/// * `config` - It takes the parsed config struct.
+/// * `cache` - It takes the cache in which the aggregated search results are stored.
+///
+/// # Error
+///
+/// Returns an error if the server could not be started on the provided listener.
+pub fn run(listener: TcpListener, config: Config, cache: Cache) -> std::io::Result<Server> {
+    let public_folder_path: &str = file_path(FileType::Theme)?;
+
+    let registry = EngineRegistry::new(&config);
+    let health = EngineHealth::build(&config);
+    let metrics = Metrics::new();
+
+    let server = HttpServer::new(move || {
+        let cors: Cors = Cors::default()
+            .allow_any_origin()
+            .allowed_methods(vec!["GET"])
+            .allowed_headers(vec![
+                header::ORIGIN,
+                header::CONTENT_TYPE,
+                header::REFERER,
+                header::COOKIE,
+            ]);
+
+        App::new()
+            // Compress the responses provided by the server for the client requests.
+            .wrap(Compress::default())
+            .wrap(Logger::default()) // added logging middleware for logging.
+            .wrap(from_fn(metrics::track_requests)) // count and time the requests per route.
+            .app_data(web::Data::new(config.clone()))
+            .app_data(web::Data::new(cache.clone()))
+            .app_data(web::Data::new(health.clone()))
+            .app_data(web::Data::new(metrics.clone()))
+            .app_data(web::Data::new(registry.clone()))
+            .wrap(cors)
+            // Serve images and static files (css and js files).
+            .service(
+                fs::Files::new("/static", format!("{}/static", public_folder_path))
+                    .show_files_listing(),
+            )
+            .service(
+                fs::Files::new("/images", format!("{}/images", public_folder_path))
+                    .show_files_listing(),
+            )
+            .service(router::robots_data) // robots.txt
+            .service(router::index) // index page
+            .service(server::routes::search::search) // search page
+            .service(server::routes::suggest::suggest) // search suggestions
+            .service(router::opensearch) // opensearch description document
+            .service(router::about) // about page
+            .service(router::settings) // settings page
+            .service(router::health_check) // health of the server and the upstream engines
+            .service(router::status) // status page
+            .service(router::prometheus_metrics) // metrics in the prometheus text format
+            .default_service(web::route().to(router::not_found)) // error page
+    })
+    // Start server on 127.0.0.1 with the user provided port number. for example 127.0.0.1:8080.
+    .listen(listener)?
+    .run();
+
+    Ok(server)
+}
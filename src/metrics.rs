@@ -0,0 +1,254 @@
+//! This module provides the metrics of the server and the upstream search engines, which are
+//! served in the prometheus text format by the metrics route.
+
+use std::time::{Duration, Instant};
+
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    middleware::Next,
+    web,
+};
+use prometheus::{
+    exponential_buckets, Encoder, Histogram, HistogramOpts, HistogramVec, IntCounter,
+    IntCounterVec, Opts, Registry, TextEncoder,
+};
+
+use crate::models::engine_models::EngineError;
+
+/// The route label of the requests which did not match any route.
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// Shared metrics of the server and the upstream search engines.
+#[derive(Clone)]
+pub struct Metrics {
+    /// The registry from which the metrics are encoded.
+    registry: Registry,
+    /// The number of requests per route, method and status.
+    http_requests: IntCounterVec,
+    /// The time it took to answer the requests per route.
+    http_request_duration: HistogramVec,
+    /// The number of requests per upstream engine.
+    engine_requests: IntCounterVec,
+    /// The number of failed requests per upstream engine and kind of error.
+    engine_errors: IntCounterVec,
+    /// The time the upstream engines took to answer per engine and outcome.
+    engine_request_duration: HistogramVec,
+    /// The number of search results served from the cache.
+    cache_hits: IntCounter,
+    /// The number of search results which were not cached.
+    cache_misses: IntCounter,
+    /// The number of results returned per query.
+    results_per_query: Histogram,
+    /// The number of duplicate results which were merged into another result.
+    merged_results: IntCounter,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    /// Creates the metrics, all of which start at zero.
+    pub fn new() -> Self {
+        let registry = Registry::new_custom(Some("crabbysearch".to_owned()), None)
+            .expect("the metrics prefix is valid");
+        let latency_buckets =
+            exponential_buckets(0.005, 2.0, 12).expect("the latency buckets are valid");
+
+        let metrics = Self {
+            http_requests: IntCounterVec::new(
+                Opts::new("http_requests_total", "Number of http requests per route"),
+                &["route", "method", "status"],
+            )
+            .expect("the metric is valid"),
+            http_request_duration: HistogramVec::new(
+                HistogramOpts::new(
+                    "http_request_duration_seconds",
+                    "Time it took to answer the http requests per route",
+                )
+                .buckets(latency_buckets.clone()),
+                &["route"],
+            )
+            .expect("the metric is valid"),
+            engine_requests: IntCounterVec::new(
+                Opts::new(
+                    "engine_requests_total",
+                    "Number of requests per upstream engine",
+                ),
+                &["engine"],
+            )
+            .expect("the metric is valid"),
+            engine_errors: IntCounterVec::new(
+                Opts::new(
+                    "engine_errors_total",
+                    "Number of failed requests per upstream engine and kind of error",
+                ),
+                &["engine", "kind"],
+            )
+            .expect("the metric is valid"),
+            engine_request_duration: HistogramVec::new(
+                HistogramOpts::new(
+                    "engine_request_duration_seconds",
+                    "Time the upstream engines took to answer per outcome (ok or the kind of error)",
+                )
+                .buckets(latency_buckets),
+                &["engine", "outcome"],
+            )
+            .expect("the metric is valid"),
+            cache_hits: IntCounter::new("cache_hits_total", "Number of search results cache hits")
+                .expect("the metric is valid"),
+            cache_misses: IntCounter::new(
+                "cache_misses_total",
+                "Number of search results cache misses",
+            )
+            .expect("the metric is valid"),
+            results_per_query: Histogram::with_opts(
+                HistogramOpts::new("results_per_query", "Number of results returned per query")
+                    .buckets(vec![0.0, 5.0, 10.0, 20.0, 50.0, 100.0, 200.0]),
+            )
+            .expect("the metric is valid"),
+            merged_results: IntCounter::new(
+                "merged_results_total",
+                "Number of duplicate results merged into the result of another engine",
+            )
+            .expect("the metric is valid"),
+            registry,
+        };
+
+        let collectors: [Box<dyn prometheus::core::Collector>; 9] = [
+            Box::new(metrics.http_requests.clone()),
+            Box::new(metrics.http_request_duration.clone()),
+            Box::new(metrics.engine_requests.clone()),
+            Box::new(metrics.engine_errors.clone()),
+            Box::new(metrics.engine_request_duration.clone()),
+            Box::new(metrics.cache_hits.clone()),
+            Box::new(metrics.cache_misses.clone()),
+            Box::new(metrics.results_per_query.clone()),
+            Box::new(metrics.merged_results.clone()),
+        ];
+        for collector in collectors {
+            metrics
+                .registry
+                .register(collector)
+                .expect("the metrics are registered once");
+        }
+
+        metrics
+    }
+
+    /// Records an answered http request.
+    ///
+    /// # Arguments
+    ///
+    /// * `route` - It takes the pattern of the route which handled the request.
+    /// * `method` - It takes the http method of the request.
+    /// * `status` - It takes the http status of the response.
+    /// * `elapsed` - It takes the time it took to answer the request.
+    pub fn observe_request(&self, route: &str, method: &str, status: u16, elapsed: Duration) {
+        self.http_requests
+            .with_label_values(&[route, method, &status.to_string()])
+            .inc();
+        self.http_request_duration
+            .with_label_values(&[route])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Records the outcome of a request to an upstream engine.
+    ///
+    /// # Arguments
+    ///
+    /// * `engine` - It takes the name of the engine.
+    /// * `latency` - It takes the time the engine took to answer.
+    /// * `error` - It takes the error the request failed with, or `None` if it succeeded.
+    pub fn observe_engine(&self, engine: &str, latency: Duration, error: Option<&EngineError>) {
+        let outcome = error.map_or("ok", EngineError::kind);
+        self.engine_requests.with_label_values(&[engine]).inc();
+        if error.is_some() {
+            self.engine_errors
+                .with_label_values(&[engine, outcome])
+                .inc();
+        }
+        self.engine_request_duration
+            .with_label_values(&[engine, outcome])
+            .observe(latency.as_secs_f64());
+    }
+
+    /// Records whether search results were served from the cache.
+    ///
+    /// # Arguments
+    ///
+    /// * `hit` - It takes whether the results were found in the cache.
+    pub fn observe_cache(&self, hit: bool) {
+        match hit {
+            true => self.cache_hits.inc(),
+            false => self.cache_misses.inc(),
+        }
+    }
+
+    /// Records the number of results returned for a query.
+    ///
+    /// # Arguments
+    ///
+    /// * `results` - It takes the number of results.
+    pub fn observe_results(&self, results: usize) {
+        self.results_per_query.observe(results as f64);
+    }
+
+    /// Records the number of duplicate results which were merged while aggregating the results.
+    ///
+    /// # Arguments
+    ///
+    /// * `merged` - It takes the number of merged results.
+    pub fn observe_merges(&self, merged: usize) {
+        self.merged_results.inc_by(merged as u64);
+    }
+
+    /// Encodes the metrics in the prometheus text format.
+    pub fn encode(&self) -> String {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("the metrics can be encoded");
+        String::from_utf8(buffer).expect("the text format is valid utf-8")
+    }
+}
+
+/// A middleware which records the count and the latency of the requests per route in the
+/// `Metrics` of the app data.
+///
+/// # Arguments
+///
+/// * `req` - It takes the request.
+/// * `next` - It takes the next service in the middleware chain.
+///
+/// # Error
+///
+/// Returns the error of the next service.
+pub async fn track_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let metrics = req.app_data::<web::Data<Metrics>>().cloned();
+    let method = req.method().to_string();
+    let started = Instant::now();
+
+    let response = next.call(req).await?;
+
+    if let Some(metrics) = metrics {
+        let route = response
+            .request()
+            .match_pattern()
+            .unwrap_or_else(|| UNMATCHED_ROUTE.to_owned());
+        metrics.observe_request(
+            &route,
+            &method,
+            response.status().as_u16(),
+            started.elapsed(),
+        );
+    }
+
+    Ok(response)
+}
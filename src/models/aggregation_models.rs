@@ -3,12 +3,13 @@
 
 use super::engine_models::EngineError;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 
 /// A named struct to store the raw scraped search results scraped search results from the
 /// upstream search engines before aggregating it.It derives the Clone trait which is needed
 /// to write idiomatic rust using `Iterators`.
 /// (href url in html in simple words).
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SearchResult {
     /// The title of the search result.
@@ -19,7 +20,11 @@ pub struct SearchResult {
     pub description: String,
     /// The names of the upstream engines from which this results were provided.
     pub engine: Vec<String>,
-    /// The td-tdf score of the result in regards to the title, url and description and the user's query
+    /// The rank (starting from 1) which each upstream engine gave this result on its result page.
+    #[serde(default)]
+    pub engine_ranks: HashMap<String, u32>,
+    /// The relevance score of the result in regards to the title, url and description and the user's query,
+    /// combined with the agreement between the upstream engines and their upstream positions.
     pub relevance_score: f32,
 }
 
@@ -30,7 +35,7 @@ impl SearchResult {
     ///
     /// * `title` - The title of the search result.
     /// * `url` - The url which is accessed when clicked on it
-    ///     (href url in html in simple words).
+    ///   (href url in html in simple words).
     /// * `description` - The description of the search result.
     /// * `engine` - The names of the upstream engines from which this results were provided.
     pub fn new(title: &str, url: &str, description: &str, engine: &[&str]) -> Self {
@@ -40,16 +45,46 @@ impl SearchResult {
             description: description.to_owned(),
             relevance_score: 0.0,
             engine: engine.iter().map(|name| name.to_string()).collect(),
+            engine_ranks: HashMap::new(),
         }
     }
 
-    /// A function which adds the engine name provided as a string into a vector of strings.
+    /// A function which records the rank an upstream engine gave this result.
     ///
     /// # Arguments
     ///
     /// * `engine` - Takes an engine name provided as a String.
-    pub fn add_engines(&mut self, engine: &str) {
-        self.engine.push(engine.to_owned())
+    /// * `rank` - Takes the rank (starting from 1) of the result on the engine's result page.
+    pub fn set_engine_rank(&mut self, engine: &str, rank: u32) {
+        self.engine_ranks.insert(engine.to_owned(), rank);
+    }
+
+    /// A function which merges a duplicate of this result, returned by another upstream engine,
+    /// into this result. The engine names and ranks are combined, the longer (more complete)
+    /// title and description are kept and a `https` url is preferred over a `http` one.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - Takes the duplicate search result.
+    pub fn merge(&mut self, other: SearchResult) {
+        for engine in other.engine {
+            if !self.engine.contains(&engine) {
+                self.engine.push(engine);
+            }
+        }
+        for (engine, rank) in other.engine_ranks {
+            let existing = self.engine_ranks.entry(engine).or_insert(rank);
+            *existing = (*existing).min(rank);
+        }
+        if other.title.len() > self.title.len() {
+            self.title = other.title;
+        }
+        if other.description.len() > self.description.len() {
+            self.description = other.description;
+        }
+        if self.url.starts_with("http://") && other.url.starts_with("https://") {
+            self.url = other.url;
+        }
     }
 
     /// A function which returns the engine name stored from the struct as a string.
@@ -68,6 +103,8 @@ pub struct EngineErrorInfo {
     /// It stores the error type which occured while fetching the result from a particular search
     /// engine.
     pub error: String,
+    /// It stores the message describing the error to the user.
+    pub message: String,
     /// It stores the name of the engine that failed to provide the requested search results.
     pub engine: String,
     /// It stores the name of the color to indicate whether how severe the particular error is (In
@@ -81,21 +118,22 @@ impl EngineErrorInfo {
     /// # Arguments
     ///
     /// * `error` - It takes the error type which occured while fetching the result from a particular
-    ///     search engine.
+    ///   search engine.
     /// * `engine` - It takes the name of the engine that failed to provide the requested search results.
     pub fn new(error: &EngineError, engine: &str) -> Self {
         Self {
-            error: match error {
-                EngineError::NoSuchEngineFound(_) => "EngineNotFound".to_owned(),
-                EngineError::RequestError => "RequestError".to_owned(),
-                EngineError::EmptyResultSet => "EmptyResultSet".to_owned(),
-                EngineError::UnexpectedError => "UnexpectedError".to_owned(),
-            },
+            error: error.kind().to_owned(),
+            message: error.to_string(),
             engine: engine.to_owned(),
             severity_color: match error {
                 EngineError::NoSuchEngineFound(_) => "red".to_owned(),
                 EngineError::RequestError => "green".to_owned(),
                 EngineError::EmptyResultSet => "blue".to_owned(),
+                EngineError::Timeout => "yellow".to_owned(),
+                EngineError::RateLimited => "orange".to_owned(),
+                EngineError::Captcha => "orange".to_owned(),
+                EngineError::ParseFailure => "red".to_owned(),
+                EngineError::TemporarilyDisabled => "grey".to_owned(),
                 EngineError::UnexpectedError => "red".to_owned(),
             },
         }
@@ -126,6 +164,10 @@ pub struct SearchResults {
     /// Stores the flag option which holds the check value that whether any search engines were
     /// selected or not.
     pub no_engines_selected: bool,
+    /// Stores the time (millisecs) each queried upstream engine took to answer, including its
+    /// retries, keyed by the engine name.
+    #[serde(default)]
+    pub engine_response_times: BTreeMap<String, u64>,
 }
 
 impl SearchResults {
@@ -134,11 +176,11 @@ impl SearchResults {
     /// # Arguments
     ///
     /// * `results` - Takes an argument of individual serializable `SearchResult` struct
-    ///     and stores it into a vector of `SearchResult` structs.
+    ///   and stores it into a vector of `SearchResult` structs.
     /// * `page_query` - Takes an argument of current page`s search query `q` provided in
-    ///     the search url.
+    ///   the search url.
     /// * `engine_errors_info` - Takes an array of structs which contains information regarding
-    ///     which engines failed with their names, reason and their severity color name.
+    ///   which engines failed with their names, reason and their severity color name.
     pub fn new(results: Vec<SearchResult>, engine_errors_info: &[EngineErrorInfo]) -> Self {
         Self {
             results,
@@ -147,6 +189,7 @@ impl SearchResults {
             filtered: Default::default(),
             safe_search_level: Default::default(),
             no_engines_selected: Default::default(),
+            engine_response_times: Default::default(),
         }
     }
 
@@ -179,8 +222,109 @@ impl SearchResults {
         self.no_engines_selected
     }
 
+    /// A setter function to set the time (millisecs) each upstream engine took to answer.
+    pub fn set_engine_response_times(&mut self, engine_response_times: BTreeMap<String, u64>) {
+        self.engine_response_times = engine_response_times;
+    }
+
     /// A setter function to set the `no_engines_selected` to true.
     pub fn set_no_engines_selected(&mut self) {
         self.no_engines_selected = true;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a result of the engine at the given upstream rank.
+    fn result(title: &str, url: &str, description: &str, engine: &str, rank: u32) -> SearchResult {
+        let mut result = SearchResult::new(title, url, description, &[engine]);
+        result.set_engine_rank(engine, rank);
+        result
+    }
+
+    #[test]
+    fn merge_combines_the_engines_and_keeps_their_best_ranks() {
+        let mut merged = result("Rust", "https://rust-lang.org/", "", "bing", 3);
+        merged.merge(result("Rust", "https://rust-lang.org/", "", "brave", 1));
+        merged.merge(result("Rust", "https://rust-lang.org/", "", "bing", 2));
+
+        assert_eq!(merged.engine, ["bing", "brave"]);
+        assert_eq!(
+            merged.engine_ranks,
+            HashMap::from([("bing".to_owned(), 2), ("brave".to_owned(), 1)])
+        );
+    }
+
+    #[test]
+    fn merge_keeps_the_longer_title_and_description() {
+        let cases = [
+            (
+                ("Rust", "Short"),
+                ("Rust Programming", "A longer description"),
+                ("Rust Programming", "A longer description"),
+            ),
+            (
+                ("Rust Programming", "A longer description"),
+                ("Rust", "Short"),
+                ("Rust Programming", "A longer description"),
+            ),
+            (
+                ("Rust", "A longer description"),
+                ("Rust Programming", "Short"),
+                ("Rust Programming", "A longer description"),
+            ),
+            (("Rust", "Same"), ("Rust", "Size"), ("Rust", "Same")),
+        ];
+
+        for ((title, description), (other_title, other_description), expected) in cases {
+            let mut merged = result(title, "https://rust-lang.org/", description, "bing", 1);
+            merged.merge(result(
+                other_title,
+                "https://rust-lang.org/",
+                other_description,
+                "brave",
+                1,
+            ));
+
+            assert_eq!(
+                (merged.title.as_str(), merged.description.as_str()),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn merge_prefers_the_https_url() {
+        let cases = [
+            (
+                "http://rust-lang.org/",
+                "https://www.rust-lang.org/",
+                "https://www.rust-lang.org/",
+            ),
+            (
+                "https://www.rust-lang.org/",
+                "http://rust-lang.org/",
+                "https://www.rust-lang.org/",
+            ),
+            (
+                "http://rust-lang.org/",
+                "http://www.rust-lang.org/",
+                "http://rust-lang.org/",
+            ),
+            (
+                "https://rust-lang.org/",
+                "https://www.rust-lang.org/",
+                "https://rust-lang.org/",
+            ),
+        ];
+
+        for (url, other_url, expected) in cases {
+            let mut merged = result("Rust", url, "", "bing", 1);
+            merged.merge(result("Rust", other_url, "", "brave", 1));
+
+            assert_eq!(merged.url, expected, "{url} merged with {other_url}");
+        }
+    }
+}
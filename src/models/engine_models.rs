@@ -2,21 +2,37 @@
 //! the upstream search engines with the search query provided by the user.
 
 use super::aggregation_models::SearchResult;
-use error_stack::{Result, ResultExt};
-use reqwest::Client;
+use crate::results::browser_profile::BrowserProfile;
+use error_stack::{Report, Result};
+use reqwest::{header::RETRY_AFTER, Client, Response, StatusCode};
 use std::{fmt, sync::Arc};
 
 /// A custom error type used for handle engine associated errors.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum EngineError {
     /// No matching engine found
     NoSuchEngineFound(String),
-    /// This variant handles all request related errors like forbidden, not found,
-    /// etc.
-    EmptyResultSet,
     /// This variant handles the not results found error provide by the upstream
     /// search engines.
+    EmptyResultSet,
+    /// This variant handles all request related errors like forbidden, not found,
+    /// etc.
     RequestError,
+    /// This variant handles the upstream search engines which did not answer within the request
+    /// timeout.
+    Timeout,
+    /// This variant handles the upstream search engines which rejected the request because too
+    /// many requests were sent (http status 429).
+    RateLimited,
+    /// This variant handles the upstream search engines which answered with a captcha (or bot
+    /// challenge) page instead of the results.
+    Captcha,
+    /// This variant handles the pages and responses of the upstream search engines which could
+    /// not be parsed, usually because the engine changed its markup.
+    ParseFailure,
+    /// This variant handles the upstream search engines which are skipped for a cool-down period
+    /// because their requests failed repeatedly (see `health::EngineHealth`).
+    TemporarilyDisabled,
     ///  This variant handles all the errors which are unexpected or occur rarely
     /// and are errors mostly related to failure in initialization of HeaderMap,
     /// Selector errors and all other errors occurring within the code handling
@@ -39,6 +55,30 @@ impl fmt::Display for EngineError {
                     "Error occurred while requesting data from upstream search engine"
                 )
             }
+            EngineError::Timeout => {
+                write!(f, "The upstream search engine did not answer in time")
+            }
+            EngineError::RateLimited => {
+                write!(
+                    f,
+                    "The upstream search engine is rate limiting the requests"
+                )
+            }
+            EngineError::Captcha => {
+                write!(f, "The upstream search engine answered with a captcha")
+            }
+            EngineError::ParseFailure => {
+                write!(
+                    f,
+                    "The results of the upstream search engine could not be parsed"
+                )
+            }
+            EngineError::TemporarilyDisabled => {
+                write!(
+                    f,
+                    "The upstream search engine is skipped after repeated failures"
+                )
+            }
             EngineError::UnexpectedError => {
                 write!(f, "An unexpected error occurred while processing the data")
             }
@@ -48,6 +88,110 @@ impl fmt::Display for EngineError {
 
 impl error_stack::Context for EngineError {}
 
+impl EngineError {
+    /// Returns the name of the kind of the error, which is shown in the UI and used in the logs.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            EngineError::NoSuchEngineFound(_) => "EngineNotFound",
+            EngineError::EmptyResultSet => "EmptyResultSet",
+            EngineError::RequestError => "RequestError",
+            EngineError::Timeout => "Timeout",
+            EngineError::RateLimited => "RateLimited",
+            EngineError::Captcha => "Captcha",
+            EngineError::ParseFailure => "ParseFailure",
+            EngineError::TemporarilyDisabled => "TemporarilyDisabled",
+            EngineError::UnexpectedError => "UnexpectedError",
+        }
+    }
+}
+
+/// The markers of the common captcha (and bot challenge) widgets which are embedded in a page.
+const CAPTCHA_MARKERS: [&str; 7] = [
+    "g-recaptcha",
+    "h-captcha",
+    "cf-turnstile",
+    "challenge-form",
+    "captcha-form",
+    "anomaly-modal",
+    "/sp/captcha",
+];
+
+/// Returns whether a page returned by an upstream search engine is a captcha (or bot challenge)
+/// page instead of a result page.
+///
+/// # Arguments
+///
+/// * `html` - It takes the html of the page.
+pub fn is_captcha(html: &str) -> bool {
+    CAPTCHA_MARKERS.iter().any(|marker| html.contains(marker))
+}
+
+/// Converts a failed request to an upstream search engine into an `EngineError`, distinguishing
+/// the requests which timed out.
+///
+/// # Arguments
+///
+/// * `error` - It takes the error returned by the client.
+fn request_error(error: reqwest::Error) -> Report<EngineError> {
+    let context = match error.is_timeout() {
+        true => EngineError::Timeout,
+        false => EngineError::RequestError,
+    };
+    Report::new(error).change_context(context)
+}
+
+/// Sends a request to an upstream search engine and checks the status of the response.
+///
+/// # Arguments
+///
+/// * `url` - It takes the url of the request.
+/// * `header_map` - It takes the http request headers.
+/// * `client` - It takes the client sending the request.
+///
+/// # Error
+///
+/// Returns a `Timeout` or `RequestError` if the request failed, a `RateLimited` error if the
+/// engine answered with the http status 429, a `Captcha` error if the engine answered with an
+/// error status and a captcha page and a `RequestError` for any other error status.
+async fn send_request(
+    url: &str,
+    header_map: reqwest::header::HeaderMap,
+    client: &Client,
+) -> Result<Response, EngineError> {
+    let response = client
+        .get(url)
+        .headers(header_map) // add spoofed headers to emulate human behavior
+        .send()
+        .await
+        .map_err(request_error)?;
+
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        let retry_after = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("unknown")
+            .to_owned();
+        return Err(
+            Report::new(EngineError::RateLimited).attach_printable(format!(
+                "{url} answered with {status}, retry after: {retry_after}"
+            )),
+        );
+    }
+
+    let body = response.text().await.unwrap_or_default();
+    let context = match is_captcha(&body) {
+        true => EngineError::Captcha,
+        false => EngineError::RequestError,
+    };
+    Err(Report::new(context).attach_printable(format!("{url} answered with {status}")))
+}
+
 /// A trait to define common behavior for all search engines.
 #[async_trait::async_trait]
 pub trait SearchEngine: Sync + Send {
@@ -67,7 +211,8 @@ pub trait SearchEngine: Sync + Send {
     /// # Error
     ///
     /// It returns the html data as a string if the upstream engine provides the data as expected
-    /// otherwise it returns a custom `EngineError`.
+    /// otherwise it returns a custom `EngineError`, which distinguishes timeouts, rate limits and
+    /// captcha pages from the other request errors.
     async fn fetch_html_from_upstream(
         &self,
         url: &str,
@@ -75,15 +220,11 @@ pub trait SearchEngine: Sync + Send {
         client: &Client,
     ) -> Result<String, EngineError> {
         // fetch the html from upstream search engine
-        Ok(client
-            .get(url)
-            .headers(header_map) // add spoofed headers to emulate human behavior
-            .send()
-            .await
-            .change_context(EngineError::RequestError)?
+        send_request(url, header_map, client)
+            .await?
             .text()
             .await
-            .change_context(EngineError::RequestError)?)
+            .map_err(request_error)
     }
 
     /// This helper function fetches/requests the json search results from the upstream search engine as a vector of bytes.
@@ -101,7 +242,8 @@ pub trait SearchEngine: Sync + Send {
     /// # Error
     ///
     /// It returns the html data as a vector of bytes if the upstream engine provides the data as expected
-    /// otherwise it returns a custom `EngineError`.
+    /// otherwise it returns a custom `EngineError`, which distinguishes timeouts, rate limits and
+    /// captcha pages from the other request errors.
     async fn fetch_json_as_bytes_from_upstream(
         &self,
         url: &str,
@@ -109,16 +251,11 @@ pub trait SearchEngine: Sync + Send {
         client: &Client,
     ) -> Result<Vec<u8>, EngineError> {
         // fetch the json response from upstream search engine
-
-        Ok(client
-            .get(url)
-            .headers(header_map) // add spoofed headers to emulate human behavior
-            .send()
-            .await
-            .change_context(EngineError::RequestError)?
+        Ok(send_request(url, header_map, client)
+            .await?
             .bytes()
             .await
-            .change_context(EngineError::RequestError)?
+            .map_err(request_error)?
             .to_vec())
     }
 
@@ -131,8 +268,10 @@ pub trait SearchEngine: Sync + Send {
     ///
     /// * `query` - Takes the user provided query to query to the upstream search engine with.
     /// * `page` - Takes an u32 as an argument.
-    /// * `user_agent` - Takes a random user agent string as an argument.
-    /// * `request_timeout` - Takes a time (secs) as a value which controls the server request timeout.
+    /// * `profile` - Takes a random browser profile whose headers are sent to the engine.
+    /// * `client` - Takes the shared reqwest client used to make the request.
+    /// * `safe_search` - Takes the safe search level (0 = off, 1 = moderate, 2 and above = strict)
+    ///   which each engine maps to its own safe search parameter.
     ///
     /// # Errors
     ///
@@ -144,38 +283,90 @@ pub trait SearchEngine: Sync + Send {
         &self,
         query: &str,
         page: u32,
-        user_agent: &str,
+        profile: &BrowserProfile,
         client: &Client,
+        safe_search: u8,
     ) -> Result<Vec<(String, SearchResult)>, EngineError>;
+
+    /// This function fetches the search suggestions (autocompletions) for a partial query from
+    /// the upstream engine. Engines which do not provide an autocomplete api keep the default
+    /// implementation, which returns no suggestions.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Takes the partial query typed by the user.
+    /// * `profile` - Takes a random browser profile whose headers are sent to the engine.
+    /// * `client` - Takes the shared reqwest client used to make the request.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `EngineError` if the upstream engine could not be reached or if the suggestions
+    /// it returned could not be parsed.
+    async fn suggestions(
+        &self,
+        _query: &str,
+        _profile: &BrowserProfile,
+        _client: &Client,
+    ) -> Result<Vec<String>, EngineError> {
+        Ok(Vec::new())
+    }
+}
+
+/// A named struct which stores the metadata of an upstream search engine in the engine registry.
+#[derive(Clone, Debug)]
+pub struct EngineInfo {
+    /// It stores the human readable name of the engine shown in the UI.
+    pub display_name: String,
+    /// It stores the categories (e.g. `general`) of search results the engine provides.
+    pub categories: Vec<String>,
+    /// It stores whether the engine can provide results for pages other than the first one.
+    pub supports_paging: bool,
+    /// It stores whether the engine can filter its results according to a safe search level.
+    pub supports_safe_search: bool,
 }
 
-/// A named struct which stores the engine struct with the name of the associated engine.
+/// A named struct which stores the engine struct with the name and the metadata of the associated
+/// engine.
 #[derive(Clone)]
 pub struct EngineHandler {
     /// It stores the engine struct wrapped in a box smart pointer as the engine struct implements
     /// the `SearchEngine` trait.
     engine: Arc<dyn SearchEngine>,
     /// It stores the name of the engine to which the struct is associated to.
-    name: &'static str,
+    name: String,
+    /// It stores the metadata of the engine.
+    info: EngineInfo,
 }
 
 impl EngineHandler {
-    /// Parses an engine name into an engine handler.
+    /// Creates an engine handler for the engine with the given name and metadata.
     ///
     /// # Arguments
     ///
-    /// * `engine_name` - It takes the name of the engine to which the struct was associated to.
-    ///
-    /// # Returns
-    ///
-    /// It returns an option either containing the value or a none if the engine is unknown
-    pub fn new(name: &'static str, engine: Arc<dyn SearchEngine>) -> Self {
-        Self { name, engine }
+    /// * `name` - It takes the name of the engine, which is the key of the engine in the registry.
+    /// * `info` - It takes the metadata of the engine.
+    /// * `engine` - It takes the engine struct implementing the `SearchEngine` trait.
+    pub fn new(name: &str, info: EngineInfo, engine: Arc<dyn SearchEngine>) -> Self {
+        Self {
+            name: name.to_owned(),
+            info,
+            engine,
+        }
+    }
+
+    /// Returns the name of the engine.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the metadata of the engine.
+    pub fn info(&self) -> &EngineInfo {
+        &self.info
     }
 
     /// This function converts the EngineHandler type into a tuple containing the engine name and
     /// the associated engine struct.
-    pub fn into_name_engine(self) -> (&'static str, Arc<dyn SearchEngine>) {
+    pub fn into_name_engine(self) -> (String, Arc<dyn SearchEngine>) {
         (self.name, self.engine)
     }
 }
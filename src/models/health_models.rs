@@ -0,0 +1,68 @@
+//! This module provides public models for serializing the health of the server and the upstream
+//! search engines, which is served by the health route and shown on the status page.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A named struct which is serialized as the json response of the health route.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthReport {
+    /// It stores whether the server is alive, which is always `ok` if it answers.
+    pub status: String,
+    /// It stores the time (secs) since the server was started.
+    pub uptime: u64,
+    /// It stores the time (secs) over which the error rate and the latencies are computed.
+    pub window: u64,
+    /// It stores the health of every registered upstream engine, keyed by the engine name.
+    pub engines: BTreeMap<String, EngineStatus>,
+}
+
+/// A named struct which stores the health of an upstream engine over the health window.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EngineStatus {
+    /// It stores the state of the engine.
+    pub state: EngineState,
+    /// It stores the time (rfc 3339) of the last successful request to the engine.
+    pub last_success: Option<String>,
+    /// It stores the kind of error (see `EngineError::kind`) of the last failed request.
+    pub last_error: Option<String>,
+    /// It stores the time (rfc 3339) of the last failed request to the engine.
+    pub last_error_at: Option<String>,
+    /// It stores the number of requests to the engine within the window.
+    pub requests: usize,
+    /// It stores the share of the requests within the window which failed.
+    pub error_rate: Option<f64>,
+    /// It stores the median time (millisecs) the engine took to answer within the window.
+    pub p50_latency: Option<u64>,
+    /// It stores the 95th percentile of the time (millisecs) the engine took to answer within
+    /// the window.
+    pub p95_latency: Option<u64>,
+}
+
+/// An enum type which provides the states of an upstream engine.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EngineState {
+    /// This variant is used for the engines which were not requested yet.
+    Unknown,
+    /// This variant is used for the engines whose last request succeeded.
+    Healthy,
+    /// This variant is used for the engines whose last request failed.
+    Failing,
+    /// This variant is used for the engines which are skipped by the circuit breaker.
+    Disabled,
+}
+
+impl EngineState {
+    /// Returns the name of the state which is shown on the status page.
+    pub fn name(&self) -> &'static str {
+        match self {
+            EngineState::Unknown => "unknown",
+            EngineState::Healthy => "healthy",
+            EngineState::Failing => "failing",
+            EngineState::Disabled => "disabled",
+        }
+    }
+}
@@ -4,4 +4,5 @@
 
 pub mod aggregation_models;
 pub mod engine_models;
+pub mod health_models;
 pub mod server_models;
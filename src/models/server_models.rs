@@ -1,7 +1,10 @@
 //! This module provides the models to parse cookies and search parameters from the search
 //! engine website.
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+
+use super::aggregation_models::SearchResults;
+
 /// A named struct which deserializes all the user provided search parameters and stores them.
 #[derive(Deserialize)]
 pub struct SearchParams {
@@ -14,4 +17,48 @@ pub struct SearchParams {
     /// It stores the search parameter `safesearch` (or safe search level in simple words) of the
     /// search url.
     pub safesearch: Option<u8>,
+    /// It stores the search parameter `format` (or the requested output format in simple words)
+    /// of the search url.
+    pub format: Option<OutputFormat>,
+}
+
+/// A named struct which deserializes the search parameters of the suggestions route.
+#[derive(Deserialize)]
+pub struct SuggestParams {
+    /// It stores the partial search query `q` typed by the user.
+    pub q: Option<String>,
+}
+
+/// An enum type which provides the different output formats in which the search results can be
+/// rendered by the search route.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// This variant renders the search results as the html search page.
+    #[default]
+    Html,
+    /// This variant serializes the search results as json.
+    Json,
+    /// This variant renders the search results as a rss 2.0 feed.
+    Rss,
+    /// This variant renders the search results as an atom feed.
+    Atom,
+}
+
+/// A named struct which is serialized as the json response of the search route. It wraps the
+/// aggregated search results together with the query and the paging information.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResponse<'a> {
+    /// It stores the search query for which the results were fetched.
+    pub query: &'a str,
+    /// It stores the current page number (starting from 1).
+    pub page: u32,
+    /// It stores the previous page number if there is one.
+    pub previous_page: Option<u32>,
+    /// It stores the next page number.
+    pub next_page: u32,
+    /// It stores the aggregated search results of the current page.
+    #[serde(flatten)]
+    pub search_results: &'a SearchResults,
 }
@@ -1,39 +1,242 @@
 //! This module provides the functionality to scrape and gathers all the results from the upstream
 //! search engines and then removes duplicate results.
 
-use super::user_agent::random_user_agent;
-use crate::config::Config;
+use super::browser_profile::random_browser_profile;
+use super::ranking::rank;
+use super::url_normalizer::canonical_url;
+use crate::config::{Config, EnginePolicy, DIRECT};
+use crate::health::EngineHealth;
+use crate::metrics::Metrics;
 use crate::models::{
     aggregation_models::{EngineErrorInfo, SearchResult, SearchResults},
     engine_models::{EngineError, EngineHandler},
 };
 
-use error_stack::Report;
-use futures::stream::FuturesUnordered;
-use reqwest::{Client, ClientBuilder};
-use std::sync::Arc;
-use tokio::{task::JoinHandle, time::Duration};
+use error_stack::{Report, ResultExt};
+use futures::stream::{FuturesUnordered, StreamExt};
+use indexmap::{map::Entry, IndexMap};
+use reqwest::{Client, ClientBuilder, Proxy};
+use std::{
+    collections::{BTreeMap, HashMap},
+    future::Future,
+    sync::{Arc, Mutex, OnceLock, PoisonError},
+};
+use tokio::{
+    task::{AbortHandle, JoinError, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// The prebuilt clients shared globally in the app, one per proxy profile, keyed by the proxy url
+/// (`None` for the engines without a proxy).
+static CLIENTS: OnceLock<Mutex<HashMap<Option<String>, Client>>> = OnceLock::new();
+
+/// The maximum number of suggestions which are returned for a query.
+const MAX_SUGGESTIONS: usize = 10;
+
+/// Returns the globally shared `Client` of the proxy profile of an engine, building it from the
+/// config on first use of the profile.
+///
+/// # Arguments
+///
+/// * `config` - It takes the parsed config struct.
+/// * `engine` - It takes the name of the engine whose proxy is used (see `Config::proxy_for`).
+///
+/// # Error
+///
+/// Returns an `EngineError` if the proxy url of the engine is invalid.
+fn client(config: &Config, engine: &str) -> Result<Client, Report<EngineError>> {
+    let proxy = config.proxy_for(engine);
+    let mut clients = CLIENTS
+        .get_or_init(Mutex::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    if let Some(client) = clients.get(&proxy.map(str::to_owned)) {
+        return Ok(client.clone());
+    }
 
-/// A constant for holding the prebuilt Client globally in the app.
-static CLIENT: std::sync::OnceLock<Client> = std::sync::OnceLock::new();
+    let mut builder = ClientBuilder::new()
+        .timeout(Duration::from_secs(config.request_timeout as u64)) // Add timeout to request to avoid DDOSing the server
+        .pool_idle_timeout(Duration::from_secs(
+            config.pool_idle_connection_timeout as u64,
+        ))
+        .tcp_keepalive(Duration::from_secs(config.tcp_connection_keep_alive as u64))
+        .connect_timeout(Duration::from_secs(config.request_timeout as u64)) // Add timeout to request to avoid DDOSing the server
+        .https_only(config.https_only)
+        .gzip(true)
+        .brotli(true)
+        .http2_adaptive_window(config.adaptive_window);
+    builder = match proxy {
+        Some(DIRECT) => builder.no_proxy(),
+        Some(proxy) => builder.proxy(
+            Proxy::all(proxy)
+                .change_context(EngineError::UnexpectedError)
+                .attach_printable_lazy(|| format!("invalid proxy url: {proxy}"))?,
+        ),
+        None => builder,
+    };
+    let client = builder
+        .build()
+        .change_context(EngineError::UnexpectedError)?;
+
+    clients.insert(proxy.map(str::to_owned), client.clone());
+    Ok(client)
+}
 
-/// Aliases for long type annotations
+/// Sends the requests of an upstream engine following the timeout, retry and backoff policy of
+/// the engine. Every attempt is aborted with a timeout error once the timeout of the policy has
+/// elapsed, and attempts which failed with a request error or a timeout are retried after a
+/// delay which starts at the backoff of the policy and is doubled after every retry. Other
+/// errors (like an empty result set or a captcha) are returned right away as a retry would not
+/// change the outcome.
+///
+/// # Arguments
+///
+/// * `engine_name` - It takes the name of the engine, which is used in the logs.
+/// * `policy` - It takes the policy of the engine.
+/// * `request` - It takes a function which sends the request to the engine.
+///
+/// # Error
+///
+/// Returns the `EngineError` of the last attempt if all attempts failed.
+async fn with_policy<T, F, Fut>(
+    engine_name: &str,
+    policy: EnginePolicy,
+    mut request: F,
+) -> Result<T, Report<EngineError>>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, Report<EngineError>>>,
+{
+    let mut attempt: u8 = 0;
+    loop {
+        let error =
+            match tokio::time::timeout(Duration::from_millis(policy.timeout), request()).await {
+                Ok(Ok(response)) => return Ok(response),
+                Ok(Err(error)) => error,
+                Err(_) => Report::new(EngineError::Timeout).attach_printable(format!(
+                    "the engine did not answer within {} ms",
+                    policy.timeout
+                )),
+            };
 
-type FutureVec =
-    FuturesUnordered<JoinHandle<Result<Vec<(String, SearchResult)>, Report<EngineError>>>>;
+        let retryable = matches!(
+            error.current_context(),
+            EngineError::RequestError | EngineError::Timeout
+        );
+        if !retryable || attempt >= policy.retries {
+            return Err(error);
+        }
+
+        let delay = policy
+            .backoff
+            .saturating_mul(1 << u32::from(attempt).min(16));
+        log::warn!(
+            "Engine Error ({engine_name}, {}): retrying in {delay} ms",
+            error.current_context().kind()
+        );
+        tokio::time::sleep(Duration::from_millis(delay)).await;
+        attempt += 1;
+    }
+}
+
+/// The results of an upstream engine, or the error it failed with.
+type EngineResponse = Result<Vec<(String, SearchResult)>, Report<EngineError>>;
+
+/// Returns the response of an engine task with the time the engine took. A task which panicked
+/// is reported as an `UnexpectedError` of the engine, so that it shows up in the engine errors,
+/// the health statistics and the metrics like any other failure.
+///
+/// # Arguments
+///
+/// * `engine` - It takes the name of the engine of the task.
+/// * `task` - It takes the outcome of the task.
+/// * `elapsed` - It takes the time which is reported if the task did not finish.
+fn task_response(
+    engine: &str,
+    task: Result<(EngineResponse, Duration), JoinError>,
+    elapsed: Duration,
+) -> (EngineResponse, Duration) {
+    task.unwrap_or_else(|error| {
+        let error = Report::new(EngineError::UnexpectedError)
+            .attach_printable(format!("the task of the engine {engine} failed: {error}"));
+        (Err(error), elapsed)
+    })
+}
+
+/// Records the outcome and the latency of the requests to an engine for the circuit breaker, the
+/// health statistics and the metrics.
+///
+/// # Arguments
+///
+/// * `health` - It takes the health state of the upstream engines, or `None` if the outcome is
+///   only recorded in the metrics.
+/// * `metrics` - It takes the metrics in which the requests to the engines are recorded.
+/// * `engine` - It takes the name of the engine.
+/// * `latency` - It takes the time the engine took to answer.
+/// * `error` - It takes the error the engine failed with, or `None` if it answered.
+fn record_outcome(
+    health: Option<&EngineHealth>,
+    metrics: &Metrics,
+    engine: &str,
+    latency: Duration,
+    error: Option<&EngineError>,
+) {
+    if let Some(health) = health {
+        health.record(engine, latency, error);
+    }
+    metrics.observe_engine(engine, latency, error);
+}
+
+/// Copies the response of an engine. The error is copied without its attachments, which were
+/// already logged when the response was first handled.
+///
+/// # Arguments
+///
+/// * `response` - It takes the name of the engine with its response.
+fn copy_response((engine, response): &(String, EngineResponse)) -> (String, EngineResponse) {
+    let response = match response {
+        Ok(results) => Ok(results.clone()),
+        Err(error) => Err(Report::new(error.current_context().clone())),
+    };
+    (engine.clone(), response)
+}
+
+/// A named struct which stores the results aggregated by `aggregate` from the engines which
+/// answered in time, with the task completing them once the late engines answered.
+pub struct Aggregation {
+    /// It stores the aggregated results of the engines which answered before the deadline (or
+    /// before the quorum was reached), the late engines are reported as timed out.
+    pub results: SearchResults,
+    /// It stores the task which awaits the late engines and resolves to the aggregated results of
+    /// all engines. It is only present if some engines were late and the late results are
+    /// configured to be cached (see `Config::cache_late_results`).
+    pub late_results: Option<JoinHandle<SearchResults>>,
+}
 
 /// The function aggregates the scraped results from the user-selected upstream search engines.
 /// These engines can be chosen either from the user interface (UI) or from the configuration file.
 /// The code handles this process by matching the selected search engines and adding them to a vector.
 /// This vector is then used to create an asynchronous task vector using `tokio::spawn`, which returns
-/// a future. This future is awaited in another loop. Once the results are collected, they are filtered
+/// a future. Each task sends its requests through the client of the proxy profile of its engine
+/// (see `Config::proxy_for`) and follows the timeout, retry and backoff policy of its engine (see
+/// `Config::engine_policy_for`) and measures the time the engine took. The engines which are
+/// temporarily disabled by the circuit breaker are skipped and reported as such. These futures are awaited
+/// in the order in which the engines answer, until the aggregation deadline has passed or the
+/// aggregation quorum of engines answered with results (see `Config::aggregation_deadline` and
+/// `Config::aggregation_quorum`). The engines which did not answer by then are reported as timed
+/// out, and are either aborted or awaited in the background to complete the results. The outcome
+/// of every engine is recorded in the engine health, a late engine is recorded once it answered
+/// in the background or as timed out if it is aborted. Once the results are collected, they are
+/// filtered
 /// to remove any errors and ensure only proper results are included. If an error is encountered, it is
 /// sent to the UI along with the name of the engine and the type of error. This information is finally
 /// placed in the returned `SearchResults` struct.
 ///
-/// Additionally, the function eliminates duplicate results. If two results are identified as coming from
-/// multiple engines, their names are combined to indicate that the results were fetched from these upstream
-/// engines. After this, all the data in the `Vec` is removed and placed into a struct that contains all
+/// Additionally, the function eliminates duplicate results, comparing their canonicalized urls (see
+/// `url_normalizer::canonical_url`). If two results are identified as coming from multiple engines, they are
+/// merged and their names are combined to indicate that the results were fetched from these upstream
+/// engines. The remaining results are then scored against the query and sorted by their relevance (see
+/// `ranking::rank`). After this, all the data in the `Vec` is removed and placed into a struct that contains all
 /// the aggregated results in a vector. Furthermore, the query used is also added to the struct. This step is
 /// necessary to ensure that the search bar in the search remains populated even when searched from the query URL.
 ///
@@ -49,107 +252,342 @@ type FutureVec =
 ///
 /// * `query` - Accepts a string to query with the above upstream search engines.
 /// * `page` - Accepts an u32 page number.
-/// * `random_delay` - Accepts a boolean value to add a random delay before making the request.
-/// * `debug` - Accepts a boolean value to enable or disable debug mode option.
-/// * `upstream_search_engines` - Accepts a vector of search engine names which was selected by the
-/// * `request_timeout` - Accepts a time (secs) as a value which controls the server request timeout.
-///     user through the UI or the config file.
+/// * `safe_search` - Accepts the safe search level which is passed on to the upstream engines.
+/// * `config` - Accepts the parsed config struct.
+/// * `health` - Accepts the health state of the upstream engines.
+/// * `requested` - Accepts whether the page was requested by the user, rather than prefetched
+///   along with it. Only the requested page probes the skipped engines and records the outcomes
+///   in the engine health, so that a single search counts once towards the failure threshold.
+/// * `metrics` - Accepts the metrics in which the requests to the engines are recorded.
+/// * `upstream_search_engines` - Accepts a vector of search engines which were selected by the
+///   user through the UI or the config file. Engines which do not support paging are only queried
+///   for the first page and engines which do not support safe search are only queried with safe
+///   search turned off.
 ///
 /// # Error
 ///
 /// Returns an error a reqwest and scraping selector errors if any error occurs in the results
-/// function in either `searx` or `duckduckgo` or both otherwise returns a `SearchResults struct`
+/// function in either `searx` or `duckduckgo` or both otherwise returns an `Aggregation` struct
 /// containing appropriate values.
+#[allow(clippy::too_many_arguments)]
 pub async fn aggregate(
     query: &str,
     page: u32,
+    safe_search: u8,
     config: actix_web::web::Data<Config>,
+    health: &EngineHealth,
+    requested: bool,
+    metrics: &Metrics,
     upstream_search_engines: &[EngineHandler],
-) -> Result<SearchResults, Box<dyn std::error::Error>> {
-    let client = CLIENT.get_or_init(|| {
-        ClientBuilder::new()
-            .timeout(Duration::from_secs(config.request_timeout as u64)) // Add timeout to request to avoid DDOSing the server
-            .pool_idle_timeout(Duration::from_secs(
-                config.pool_idle_connection_timeout as u64,
-            ))
-            .tcp_keepalive(Duration::from_secs(config.tcp_connection_keep_alive as u64))
-            .connect_timeout(Duration::from_secs(config.request_timeout as u64)) // Add timeout to request to avoid DDOSing the server
-            .https_only(true)
-            .gzip(true)
-            .brotli(true)
-            .http2_adaptive_window(config.adaptive_window)
-            .build()
-            .unwrap()
+) -> Result<Aggregation, Box<dyn std::error::Error>> {
+    let query: Arc<String> = Arc::new(query.to_string());
+    // engines which cannot provide the requested page or filter their results are skipped.
+    let upstream_search_engines = upstream_search_engines.iter().filter(|engine_handler| {
+        let info = engine_handler.info();
+        (page == 0 || info.supports_paging) && (safe_search == 0 || info.supports_safe_search)
     });
 
-    let user_agent: &str = random_user_agent();
+    // engines whose requests kept failing are skipped until their cool-down period has passed,
+    // the prefetched pages skip them without probing them.
+    let (upstream_search_engines, disabled): (Vec<&EngineHandler>, Vec<&EngineHandler>) =
+        upstream_search_engines.partition(|engine_handler| match requested {
+            true => health.allow(engine_handler.name()),
+            false => !health.is_disabled(engine_handler.name()),
+        });
+    let recorded_health = requested.then_some(health);
+    let disabled: Vec<String> = disabled
+        .into_iter()
+        .map(|engine_handler| engine_handler.name().to_owned())
+        .collect();
 
-    let mut names: Vec<&str> = Vec::with_capacity(0);
+    // the engines which have not answered yet, in the order in which they were selected.
+    let mut pending: IndexMap<String, AbortHandle> = IndexMap::new();
 
-    // create tasks for upstream result fetching
-    let tasks: FutureVec = FutureVec::new();
+    // create tasks for upstream result fetching, keeping the name of the engine with its task.
+    let mut tasks: FuturesUnordered<_> = upstream_search_engines
+        .into_iter()
+        .map(|engine_handler| {
+            let (name, search_engine) = engine_handler.clone().into_name_engine();
+            let policy = config.engine_policy_for(&name);
+            let client = client(&config, &name);
+            let profile = random_browser_profile();
+            let engine_name = name.clone();
+            let query_partially_cloned = query.clone();
+            let task = tokio::spawn(async move {
+                let started = Instant::now();
+                let result = match client {
+                    Ok(client) => {
+                        with_policy(&engine_name, policy, || {
+                            search_engine.results(
+                                &query_partially_cloned,
+                                page,
+                                profile,
+                                &client,
+                                safe_search,
+                            )
+                        })
+                        .await
+                    }
+                    Err(error) => Err(error),
+                };
+                (result, started.elapsed())
+            });
+            pending.insert(name.clone(), task.abort_handle());
+            async move { (name, task.await) }
+        })
+        .collect();
+    let engine_order: Vec<String> = pending.keys().cloned().collect();
 
-    let query: Arc<String> = Arc::new(query.to_string());
-    for engine_handler in upstream_search_engines {
-        let (name, search_engine) = engine_handler.clone().into_name_engine();
-        names.push(name);
-        let query_partially_cloned = query.clone();
-        tasks.push(tokio::spawn(async move {
-            search_engine
-                .results(&query_partially_cloned, page, user_agent, client)
-                .await
-        }));
+    // get upstream responses with the time (millisecs) each engine took, until the deadline has
+    // passed or the quorum of engines answered with results.
+    let started = Instant::now();
+    let deadline = config
+        .aggregation_deadline
+        .map(|deadline| started + Duration::from_millis(deadline));
+    let mut responses: Vec<(String, EngineResponse)> = Vec::with_capacity(tasks.len());
+    let mut engine_response_times: BTreeMap<String, u64> = BTreeMap::new();
+    let mut answered = 0;
+
+    while !matches!(config.aggregation_quorum, Some(quorum) if answered >= quorum) {
+        let next = match deadline {
+            Some(deadline) => match tokio::time::timeout_at(deadline, tasks.next()).await {
+                Ok(next) => next,
+                Err(_) => break,
+            },
+            None => tasks.next().await,
+        };
+        let Some((name, task)) = next else { break };
+
+        pending.shift_remove(&name);
+        let (result, elapsed) = task_response(&name, task, started.elapsed());
+        answered += usize::from(result.is_ok());
+        engine_response_times.insert(name.clone(), elapsed.as_millis() as u64);
+        responses.push((name, result))
     }
 
-    // get upstream responses
-    let mut responses = Vec::with_capacity(tasks.len());
+    let late: Vec<String> = pending.keys().cloned().collect();
+    if !late.is_empty() {
+        log::info!("Engines did not answer in time: {}", late.join(", "));
+    }
 
-    for task in tasks {
-        if let Ok(result) = task.await {
-            responses.push(result)
-        }
+    // the outcome and the latency of every engine which answered in time are recorded, the late
+    // engines are recorded once their outcome is known.
+    for (engine, response) in &responses {
+        let latency = Duration::from_millis(engine_response_times[engine]);
+        let error = response.as_ref().err().map(Report::current_context);
+        record_outcome(recorded_health, metrics, engine, latency, error);
     }
 
+    // the responses are only copied if the late engines are awaited, as the complete results
+    // are built again from all the responses.
+    let complete_responses: Option<Vec<(String, EngineResponse)>> =
+        match !late.is_empty() && config.cache_late_results {
+            true => Some(responses.iter().map(copy_response).collect()),
+            false => None,
+        };
+
+    let (results, merged) = build_results(
+        &query,
+        &engine_order,
+        responses,
+        &late,
+        &disabled,
+        engine_response_times.clone(),
+        &config,
+    );
+    metrics.observe_merges(merged);
+
+    // the late engines are awaited in the background to complete the results, or aborted.
+    let late_results = match complete_responses {
+        Some(mut responses) => {
+            let (health, metrics) = (recorded_health.cloned(), metrics.clone());
+            Some(tokio::spawn(async move {
+                while let Some((name, task)) = tasks.next().await {
+                    let (result, elapsed) = task_response(&name, task, started.elapsed());
+                    let error = result.as_ref().err().map(Report::current_context);
+                    record_outcome(health.as_ref(), &metrics, &name, elapsed, error);
+                    engine_response_times.insert(name.clone(), elapsed.as_millis() as u64);
+                    responses.push((name, result))
+                }
+                build_results(
+                    &query,
+                    &engine_order,
+                    responses,
+                    &[],
+                    &disabled,
+                    engine_response_times,
+                    &config,
+                )
+                .0
+            }))
+        }
+        None => {
+            pending.values().for_each(AbortHandle::abort);
+            for engine in &late {
+                let timeout = Some(&EngineError::Timeout);
+                record_outcome(recorded_health, metrics, engine, started.elapsed(), timeout);
+            }
+            None
+        }
+    };
+
+    Ok(Aggregation {
+        results,
+        late_results,
+    })
+}
+
+/// Builds the search results from the responses of the upstream engines, merging the duplicate
+/// results and ranking them. The responses are merged in the order in which the engines were
+/// selected, regardless of the order in which they answered. It returns the results with the
+/// number of duplicate results which were merged.
+///
+/// # Arguments
+///
+/// * `query` - It takes the user provided query, which the results are ranked against.
+/// * `engine_order` - It takes the names of the queried engines in the order they were selected.
+/// * `responses` - It takes the names of the engines which answered with their response, whose
+///   results are moved into the search results.
+/// * `late` - It takes the names of the engines which did not answer in time, which are reported
+///   as timed out.
+/// * `disabled` - It takes the names of the engines which were skipped by the circuit breaker.
+/// * `engine_response_times` - It takes the time (millisecs) each engine took to answer.
+/// * `config` - It takes the parsed config struct.
+fn build_results(
+    query: &str,
+    engine_order: &[String],
+    mut responses: Vec<(String, EngineResponse)>,
+    late: &[String],
+    disabled: &[String],
+    engine_response_times: BTreeMap<String, u64>,
+    config: &Config,
+) -> (SearchResults, usize) {
     // aggregate search results, removing duplicates and handling errors the upstream engines returned
-    let mut result_map: Vec<(String, SearchResult)> = Vec::new();
+    // results keyed by their canonical url, kept in the order in which they were first seen
+    let mut result_map: IndexMap<String, SearchResult> = IndexMap::new();
     let mut engine_errors_info: Vec<EngineErrorInfo> = Vec::new();
+    let mut merged = 0;
 
-    let mut handle_error = |error: &Report<EngineError>, engine_name: &'static str| {
-        log::error!("Engine Error: {:?}", error);
-        engine_errors_info.push(EngineErrorInfo::new(
-            error.downcast_ref::<EngineError>().unwrap(),
-            engine_name,
-        ));
+    let mut handle_error = |error: &Report<EngineError>, engine_name: &str| {
+        let engine_error = error.current_context();
+        log::error!(
+            "Engine Error ({engine_name}, {}): {:?}",
+            engine_error.kind(),
+            error
+        );
+        engine_errors_info.push(EngineErrorInfo::new(engine_error, engine_name));
     };
 
-    for _ in 0..responses.len() {
-        let response = responses.pop().unwrap();
-        let engine = names.pop().unwrap();
-
-        if result_map.is_empty() {
-            match response {
-                Ok(results) => result_map = results,
-                Err(error) => handle_error(&error, engine),
-            };
-            continue;
-        }
+    responses.sort_by_key(|(engine, _)| engine_order.iter().position(|name| name == engine));
 
+    for (engine, response) in responses {
         match response {
             Ok(result) => {
-                result.into_iter().for_each(|(key, value)| {
-                    match result_map.iter().find(|(key_s, _)| key_s == &key) {
-                        Some(value) => value.1.to_owned().add_engines(engine),
-                        None => result_map.push((key, value)),
-                    };
-                });
+                result
+                    .into_iter()
+                    .enumerate()
+                    .for_each(|(position, (url, mut value))| {
+                        value.set_engine_rank(&engine, position as u32 + 1);
+                        match result_map.entry(canonical_url(&url)) {
+                            Entry::Occupied(mut existing) => {
+                                existing.get_mut().merge(value);
+                                merged += 1;
+                            }
+                            Entry::Vacant(entry) => {
+                                entry.insert(value);
+                            }
+                        };
+                    });
             }
-            Err(error) => handle_error(&error, engine),
+            Err(error) => handle_error(&error, &engine),
         };
     }
 
-    let mut results: Vec<SearchResult> =
-        result_map.iter().map(|(_, value)| value.clone()).collect();
+    for engine in late {
+        engine_errors_info.push(EngineErrorInfo::new(&EngineError::Timeout, engine));
+    }
+    for engine in disabled {
+        engine_errors_info.push(EngineErrorInfo::new(
+            &EngineError::TemporarilyDisabled,
+            engine,
+        ));
+    }
+
+    let mut results: Vec<SearchResult> = result_map.into_values().collect();
+
+    rank(query, &mut results, &config.engine_weights);
+    let mut search_results = SearchResults::new(results, &engine_errors_info);
+    search_results.set_engine_response_times(engine_response_times);
+    (search_results, merged)
+}
+
+/// The function aggregates the search suggestions (autocompletions) for a partial query from the
+/// user-selected upstream search engines which support suggestions. The suggestions of all engines
+/// are interleaved so that the top suggestions of every engine come first, duplicates are removed
+/// (ignoring case) and the list is truncated to `MAX_SUGGESTIONS` entries.
+///
+/// Engines which fail to provide suggestions are logged and otherwise ignored, as suggestions are
+/// a best-effort feature.
+///
+/// # Arguments
+///
+/// * `query` - Accepts the partial query typed by the user.
+/// * `config` - Accepts the parsed config struct.
+/// * `upstream_search_engines` - Accepts a vector of search engines which were selected by the
+///   user through the UI or the config file.
+pub async fn aggregate_suggestions(
+    query: &str,
+    config: &Config,
+    upstream_search_engines: &[EngineHandler],
+) -> Vec<String> {
+    let query: Arc<String> = Arc::new(query.to_string());
+    let tasks: Vec<_> = upstream_search_engines
+        .iter()
+        .map(|engine_handler| {
+            let (name, search_engine) = engine_handler.clone().into_name_engine();
+            let client = client(config, &name);
+            let profile = random_browser_profile();
+            let query_partially_cloned = query.clone();
+            (
+                name,
+                tokio::spawn(async move {
+                    search_engine
+                        .suggestions(&query_partially_cloned, profile, &client?)
+                        .await
+                }),
+            )
+        })
+        .collect();
+
+    let mut engine_suggestions: Vec<std::vec::IntoIter<String>> = Vec::with_capacity(tasks.len());
+    for (name, task) in tasks {
+        match task.await {
+            Ok(Ok(suggestions)) => engine_suggestions.push(suggestions.into_iter()),
+            Ok(Err(error)) => log::error!(
+                "Engine Error ({name} suggestions, {}): {:?}",
+                error.current_context().kind(),
+                error
+            ),
+            Err(error) => log::error!("Engine Error ({name} suggestions): {:?}", error),
+        }
+    }
+
+    let mut suggestions: Vec<String> = Vec::with_capacity(MAX_SUGGESTIONS);
+    while suggestions.len() < MAX_SUGGESTIONS && !engine_suggestions.is_empty() {
+        engine_suggestions.retain_mut(|engine| match engine.next() {
+            Some(suggestion) => {
+                if suggestions.len() < MAX_SUGGESTIONS
+                    && !suggestions
+                        .iter()
+                        .any(|existing| existing.eq_ignore_ascii_case(&suggestion))
+                {
+                    suggestions.push(suggestion);
+                }
+                true
+            }
+            None => false,
+        });
+    }
 
-    results.sort_by(|a, b| a.description.len().cmp(&b.description.len()));
-    Ok(SearchResults::new(results, &engine_errors_info))
+    suggestions
 }
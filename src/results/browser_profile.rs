@@ -0,0 +1,141 @@
+//! This module provides the browser profiles whose headers are sent to the upstream engines, so
+//! that each request looks like it was sent by a real browser.
+
+use error_stack::{Result, ResultExt};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+
+use crate::models::engine_models::EngineError;
+
+/// The accept header of the chromium based browsers.
+const CHROMIUM_ACCEPT: &str = "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7";
+
+/// The accept header of firefox.
+const FIREFOX_ACCEPT: &str =
+    "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,image/png,image/svg+xml,*/*;q=0.8";
+
+/// The accept header of safari.
+const SAFARI_ACCEPT: &str = "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8";
+
+/// The browser profiles from which a random one is chosen for each request.
+static PROFILES: [BrowserProfile; 6] = [
+    BrowserProfile {
+        user_agent: "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/130.0.0.0 Safari/537.36",
+        accept: CHROMIUM_ACCEPT,
+        accept_language: "en-US,en;q=0.9",
+        client_hints: Some(ClientHints {
+            brands: r#""Chromium";v="130", "Google Chrome";v="130", "Not?A_Brand";v="99""#,
+            platform: r#""Windows""#,
+        }),
+    },
+    BrowserProfile {
+        user_agent: "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/130.0.0.0 Safari/537.36",
+        accept: CHROMIUM_ACCEPT,
+        accept_language: "en-GB,en-US;q=0.9,en;q=0.8",
+        client_hints: Some(ClientHints {
+            brands: r#""Chromium";v="130", "Google Chrome";v="130", "Not?A_Brand";v="99""#,
+            platform: r#""macOS""#,
+        }),
+    },
+    BrowserProfile {
+        user_agent: "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/130.0.0.0 Safari/537.36 Edg/130.0.0.0",
+        accept: CHROMIUM_ACCEPT,
+        accept_language: "en-US,en;q=0.9",
+        client_hints: Some(ClientHints {
+            brands: r#""Chromium";v="130", "Microsoft Edge";v="130", "Not?A_Brand";v="99""#,
+            platform: r#""Windows""#,
+        }),
+    },
+    BrowserProfile {
+        user_agent: "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:131.0) Gecko/20100101 Firefox/131.0",
+        accept: FIREFOX_ACCEPT,
+        accept_language: "en-US,en;q=0.5",
+        client_hints: None,
+    },
+    BrowserProfile {
+        user_agent: "Mozilla/5.0 (X11; Linux x86_64; rv:131.0) Gecko/20100101 Firefox/131.0",
+        accept: FIREFOX_ACCEPT,
+        accept_language: "en-GB,en;q=0.5",
+        client_hints: None,
+    },
+    BrowserProfile {
+        user_agent: "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/18.0 Safari/605.1.15",
+        accept: SAFARI_ACCEPT,
+        accept_language: "en-US,en;q=0.9",
+        client_hints: None,
+    },
+];
+
+/// A named struct which stores the user agent client hints, which only the chromium based
+/// browsers send.
+#[derive(Debug)]
+pub struct ClientHints {
+    /// It stores the brands and major versions of the browser (the `Sec-CH-UA` header).
+    pub brands: &'static str,
+    /// It stores the operating system of the browser (the `Sec-CH-UA-Platform` header).
+    pub platform: &'static str,
+}
+
+/// A named struct which stores a coherent set of headers sent by a browser, so that the user
+/// agent matches the other headers of the request.
+#[derive(Debug)]
+pub struct BrowserProfile {
+    /// It stores the user agent of the browser.
+    pub user_agent: &'static str,
+    /// It stores the accept header the browser sends when navigating to a page.
+    pub accept: &'static str,
+    /// It stores the accept language header of the browser.
+    pub accept_language: &'static str,
+    /// It stores the client hints of the browser, if it sends them.
+    pub client_hints: Option<ClientHints>,
+}
+
+impl BrowserProfile {
+    /// Builds the request headers of the profile, with the engine specific headers (like the
+    /// cookies or the content type) added to them. An engine specific header replaces the
+    /// header of the profile with the same name.
+    ///
+    /// # Arguments
+    ///
+    /// * `headers` - It takes the engine specific headers as name and value pairs.
+    ///
+    /// # Error
+    ///
+    /// Returns an `EngineError` if a header is not a valid http header.
+    pub fn header_map(&self, headers: &[(&str, &str)]) -> Result<HeaderMap, EngineError> {
+        let profile_headers = [
+            ("User-Agent", self.user_agent),
+            ("Accept", self.accept),
+            ("Accept-Language", self.accept_language),
+        ];
+        let client_hints = self.client_hints.iter().flat_map(|hints| {
+            [
+                ("Sec-CH-UA", hints.brands),
+                ("Sec-CH-UA-Mobile", "?0"),
+                ("Sec-CH-UA-Platform", hints.platform),
+            ]
+        });
+
+        let mut header_map = HeaderMap::new();
+        for (name, value) in profile_headers
+            .into_iter()
+            .chain(client_hints)
+            .chain(headers.iter().copied())
+        {
+            header_map.insert(
+                HeaderName::try_from(name).change_context(EngineError::UnexpectedError)?,
+                HeaderValue::try_from(value).change_context(EngineError::UnexpectedError)?,
+            );
+        }
+        Ok(header_map)
+    }
+}
+
+/// A function to choose a random browser profile to improve privacy of the user. It is called
+/// for each request, so that the upstream engines see different browsers.
+///
+/// # Returns
+///
+/// A randomly chosen browser profile.
+pub fn random_browser_profile() -> &'static BrowserProfile {
+    &PROFILES[fastrand::usize(..PROFILES.len())]
+}
@@ -0,0 +1,250 @@
+//! This module provides the functionality to filter search queries and search results with the
+//! regexes listed in the `allowlist.txt` and `blocklist.txt` files. A query or result is blocked if
+//! it matches a regex of the blocklist and none of the allowlist.
+
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+use crate::{
+    handler::{file_path, FileType},
+    models::aggregation_models::SearchResult,
+};
+
+/// A static variable which stores the regexes of the allowlist and the blocklist, which are read
+/// once on first use.
+static FILTER_LISTS: OnceLock<FilterLists> = OnceLock::new();
+
+/// A named struct which stores the compiled regexes of the allowlist and the blocklist.
+#[derive(Debug, Default)]
+pub struct FilterLists {
+    /// The regexes of the allowlist, which take precedence over the blocklist.
+    allow: Vec<Regex>,
+    /// The regexes of the blocklist.
+    block: Vec<Regex>,
+}
+
+impl FilterLists {
+    /// Builds the filter lists from the contents of the allowlist and the blocklist files.
+    ///
+    /// # Arguments
+    ///
+    /// * `allowlist` - It takes the contents of the allowlist file.
+    /// * `blocklist` - It takes the contents of the blocklist file.
+    pub fn parse(allowlist: &str, blocklist: &str) -> Self {
+        Self {
+            allow: parse_list(allowlist, "allowlist"),
+            block: parse_list(blocklist, "blocklist"),
+        }
+    }
+
+    /// Reads the filter lists from the allowlist and the blocklist files. A missing file is
+    /// treated as an empty list.
+    ///
+    /// # Arguments
+    ///
+    /// * `allowlist` - It takes the path of the allowlist file.
+    /// * `blocklist` - It takes the path of the blocklist file.
+    pub fn read(allowlist: &str, blocklist: &str) -> Self {
+        Self::parse(&read_list(allowlist), &read_list(blocklist))
+    }
+
+    /// Returns whether the text matches the blocklist without matching the allowlist.
+    ///
+    /// # Arguments
+    ///
+    /// * `texts` - It takes the texts to be checked, a match on any of them counts.
+    pub fn is_blocked(&self, texts: &[&str]) -> bool {
+        let matches = |list: &[Regex]| {
+            list.iter()
+                .any(|regex| texts.iter().any(|text| regex.is_match(text)))
+        };
+        matches(&self.block) && !matches(&self.allow)
+    }
+
+    /// Removes all the search results whose url, title or description is blocked.
+    ///
+    /// # Arguments
+    ///
+    /// * `results` - It takes the search results to be filtered.
+    ///
+    /// # Returns
+    ///
+    /// It returns whether any search result has been removed.
+    pub fn filter(&self, results: &mut Vec<SearchResult>) -> bool {
+        let count = results.len();
+
+        results
+            .retain(|result| !self.is_blocked(&[&result.url, &result.title, &result.description]));
+
+        results.len() != count
+    }
+}
+
+/// Reads the contents of the list file at the given path. A missing or unreadable file is logged
+/// and treated as an empty list.
+///
+/// # Arguments
+///
+/// * `path` - It takes the path of the list file.
+fn read_list(path: &str) -> String {
+    std::fs::read_to_string(path).unwrap_or_else(|error| {
+        log::error!("Could not read the filter list {path}: {error}");
+        String::new()
+    })
+}
+
+/// Parses the regexes (one per line) of a list. Empty lines and lines starting with `#` are
+/// ignored and invalid regexes are logged and skipped.
+///
+/// # Arguments
+///
+/// * `contents` - It takes the contents of the list file.
+/// * `source` - It takes the name of the list, which is used in the logs.
+fn parse_list(contents: &str, source: &str) -> Vec<Regex> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| match Regex::new(line) {
+            Ok(regex) => Some(regex),
+            Err(error) => {
+                log::error!("Invalid regex '{line}' in the filter list {source}: {error}");
+                None
+            }
+        })
+        .collect()
+}
+
+/// Loads the filter lists from the given allowlist and blocklist files instead of the ones found
+/// in the config directories. It has to be called before the first search which is filtered.
+///
+/// # Arguments
+///
+/// * `allowlist` - It takes the path of the allowlist file.
+/// * `blocklist` - It takes the path of the blocklist file.
+///
+/// # Returns
+///
+/// It returns whether the filter lists have been loaded, which is not the case if they had already
+/// been loaded.
+pub fn load_filter_lists(allowlist: &str, blocklist: &str) -> bool {
+    FILTER_LISTS
+        .set(FilterLists::read(allowlist, blocklist))
+        .is_ok()
+}
+
+/// Returns the filter lists, reading them from the config directories on first use.
+fn filter_lists() -> &'static FilterLists {
+    FILTER_LISTS.get_or_init(|| {
+        let read = |file_type| file_path(file_type).map(read_list).unwrap_or_default();
+        FilterLists::parse(&read(FileType::AllowList), &read(FileType::BlockList))
+    })
+}
+
+/// Returns whether the search query is blocked by the filter lists.
+///
+/// # Arguments
+///
+/// * `query` - It takes the user's search query.
+pub fn is_query_blocked(query: &str) -> bool {
+    filter_lists().is_blocked(&[query])
+}
+
+/// Removes all the search results whose url, title or description is blocked by the filter lists.
+///
+/// # Arguments
+///
+/// * `results` - It takes the aggregated search results to be filtered.
+///
+/// # Returns
+///
+/// It returns whether any search result has been removed.
+pub fn filter_results(results: &mut Vec<SearchResult>) -> bool {
+    filter_lists().filter(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The blocklist of the tests, with a comment, an empty line and an invalid regex.
+    const BLOCKLIST: &str = "# gambling\n(?i)casino\n\nexample\\.com/ads\n[invalid\n";
+
+    /// The allowlist of the tests, which lets a page about casinos through.
+    const ALLOWLIST: &str = "wikipedia\\.org\n";
+
+    /// Builds a search result of the given title and url.
+    fn result(title: &str, url: &str) -> SearchResult {
+        SearchResult::new(title, url, "", &["bing"])
+    }
+
+    /// Returns the urls of the search results.
+    fn urls(results: &[SearchResult]) -> Vec<&str> {
+        results.iter().map(|result| result.url.as_str()).collect()
+    }
+
+    #[test]
+    fn parse_skips_the_comments_and_the_invalid_regexes() {
+        let lists = FilterLists::parse(ALLOWLIST, BLOCKLIST);
+
+        assert_eq!(lists.allow.len(), 1);
+        assert_eq!(lists.block.len(), 2);
+    }
+
+    #[test]
+    fn filter_removes_the_blocked_results() {
+        let lists = FilterLists::parse("", BLOCKLIST);
+        let mut results = vec![
+            result("Best Casino Bonus", "https://casino.test/"),
+            result("Rust", "https://rust-lang.org/"),
+            result("Ad", "https://example.com/ads/1"),
+        ];
+
+        assert!(lists.filter(&mut results));
+        assert_eq!(urls(&results), ["https://rust-lang.org/"]);
+    }
+
+    #[test]
+    fn filter_reports_when_nothing_was_removed() {
+        let lists = FilterLists::parse("", BLOCKLIST);
+        let mut results = vec![result("Rust", "https://rust-lang.org/")];
+
+        assert!(!lists.filter(&mut results));
+        assert_eq!(results.len(), 1);
+    }
+
+    #[test]
+    fn the_allowlist_takes_precedence_over_the_blocklist() {
+        let lists = FilterLists::parse(ALLOWLIST, BLOCKLIST);
+        let mut results = vec![
+            result("Casino", "https://en.wikipedia.org/wiki/Casino"),
+            result("Casino", "https://casino.test/"),
+        ];
+
+        assert!(lists.filter(&mut results));
+        assert_eq!(urls(&results), ["https://en.wikipedia.org/wiki/Casino"]);
+    }
+
+    #[test]
+    fn a_query_is_blocked_unless_it_is_allowed() {
+        let lists = FilterLists::parse(ALLOWLIST, BLOCKLIST);
+
+        let cases = [
+            ("online casino", true),
+            ("CASINO", true),
+            ("casino wikipedia.org", false),
+            ("rust", false),
+        ];
+        for (query, blocked) in cases {
+            assert_eq!(lists.is_blocked(&[query]), blocked, "{query}");
+        }
+    }
+
+    #[test]
+    fn empty_lists_block_nothing() {
+        let lists = FilterLists::default();
+
+        assert!(!lists.is_blocked(&["casino"]));
+    }
+}
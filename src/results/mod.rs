@@ -3,4 +3,7 @@
 //! provides various models to aggregate search results into a standardized form.
 
 pub mod aggregator;
-mod user_agent;
+pub mod browser_profile;
+pub mod filter;
+pub mod ranking;
+pub mod url_normalizer;
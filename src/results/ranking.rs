@@ -0,0 +1,316 @@
+//! This module provides the functionality to rank the aggregated search results against the user's
+//! query. The text relevance is computed with the BM25 ranking function over the title, url and
+//! description of each result and is combined with the rankings of the upstream engines through
+//! reciprocal rank fusion.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{config::engine_setting, models::aggregation_models::SearchResult};
+
+/// The BM25 term frequency saturation parameter.
+const K1: f32 = 1.2;
+/// The BM25 document length normalization parameter.
+const B: f32 = 0.75;
+
+/// The weights of the title, url and description fields in the BM25 score.
+const FIELD_WEIGHTS: [f32; 3] = [2.0, 1.0, 1.0];
+
+/// The reciprocal rank fusion constant, which dampens the influence of the top upstream ranks.
+const RRF_K: f32 = 60.0;
+
+/// The weight of the normalized BM25 text relevance in the final score.
+const TEXT_WEIGHT: f32 = 0.4;
+/// The weight of the normalized reciprocal rank fusion score in the final score.
+const FUSION_WEIGHT: f32 = 0.6;
+
+/// Splits a text into lowercase alphanumeric tokens, skipping anything inside html tags as the
+/// titles and descriptions scraped from the upstream engines may still contain markup.
+///
+/// # Arguments
+///
+/// * `text` - It takes the text to be tokenized.
+fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut inside_tag = false;
+
+    for c in text.chars() {
+        match c {
+            '<' => inside_tag = true,
+            '>' => inside_tag = false,
+            c if !inside_tag && c.is_alphanumeric() => {
+                current.extend(c.to_lowercase());
+                continue;
+            }
+            _ => {}
+        }
+        if !current.is_empty() {
+            tokens.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+
+    tokens
+}
+
+/// Scores the results against the query, stores the score in `relevance_score` of each result and
+/// sorts the results by it in descending order. Results with an equal score keep their order.
+///
+/// The score is a weighted sum of:
+///
+/// * the BM25 score of the query against the title, url and description, normalized by the best
+///   BM25 score in the result set,
+/// * the reciprocal rank fusion of the upstream rankings, `sum(weight / (RRF_K + rank))` over every
+///   engine which returned the result, normalized by the best fusion score in the result set. This
+///   lifts results which several engines rank highly above results of a single engine.
+///
+/// # Arguments
+///
+/// * `query` - It takes the user's search query.
+/// * `results` - It takes the aggregated search results to be ranked.
+/// * `engine_weights` - It takes the weight of each upstream engine in the rank fusion, keyed by
+///   the engine names which are matched case-insensitively. Engines without an explicit weight
+///   have a weight of `1.0`.
+pub fn rank(query: &str, results: &mut [SearchResult], engine_weights: &HashMap<String, f32>) {
+    let query_terms: HashSet<String> = tokenize(query).into_iter().collect();
+
+    let documents: Vec<[Vec<String>; 3]> = results
+        .iter()
+        .map(|result| {
+            [
+                tokenize(&result.title),
+                tokenize(&result.url),
+                tokenize(&result.description),
+            ]
+        })
+        .collect();
+
+    let document_count = documents.len() as f32;
+    let mut average_lengths = [0.0_f32; 3];
+    for fields in &documents {
+        for (average, field) in average_lengths.iter_mut().zip(fields) {
+            *average += field.len() as f32 / document_count;
+        }
+    }
+
+    let document_frequency: HashMap<&str, f32> = query_terms
+        .iter()
+        .map(|term| {
+            let frequency = documents
+                .iter()
+                .filter(|fields| fields.iter().flatten().any(|token| token == term))
+                .count();
+            (term.as_str(), frequency as f32)
+        })
+        .collect();
+
+    let text_scores: Vec<f32> = documents
+        .iter()
+        .map(|fields| {
+            query_terms
+                .iter()
+                .map(|term| {
+                    let frequency = document_frequency[term.as_str()];
+                    let idf = (1.0 + (document_count - frequency + 0.5) / (frequency + 0.5)).ln();
+
+                    let weighted_tf: f32 = fields
+                        .iter()
+                        .zip(FIELD_WEIGHTS.iter().zip(average_lengths))
+                        .map(|(field, (weight, average_length))| {
+                            let tf = field.iter().filter(|token| *token == term).count() as f32;
+                            let length_norm = match average_length > 0.0 {
+                                true => 1.0 - B + B * field.len() as f32 / average_length,
+                                false => 1.0,
+                            };
+                            weight * tf * (K1 + 1.0) / (tf + K1 * length_norm)
+                        })
+                        .sum();
+
+                    idf * weighted_tf
+                })
+                .sum()
+        })
+        .collect();
+
+    let fusion_scores: Vec<f32> = results
+        .iter()
+        .map(|result| {
+            result
+                .engine_ranks
+                .iter()
+                .map(|(engine, rank)| {
+                    engine_setting(engine_weights, engine)
+                        .copied()
+                        .unwrap_or(1.0)
+                        / (RRF_K + *rank as f32)
+                })
+                .sum()
+        })
+        .collect();
+
+    let max_text_score = text_scores.iter().copied().fold(0.0_f32, f32::max);
+    let max_fusion_score = fusion_scores.iter().copied().fold(0.0_f32, f32::max);
+
+    for ((result, text_score), fusion_score) in
+        results.iter_mut().zip(text_scores).zip(fusion_scores)
+    {
+        let text = match max_text_score > 0.0 {
+            true => text_score / max_text_score,
+            false => 0.0,
+        };
+        let fusion = match max_fusion_score > 0.0 {
+            true => fusion_score / max_fusion_score,
+            false => 0.0,
+        };
+
+        result.relevance_score = TEXT_WEIGHT * text + FUSION_WEIGHT * fusion;
+    }
+
+    results.sort_by(|a, b| b.relevance_score.total_cmp(&a.relevance_score));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a result returned by the given engines at the given upstream ranks.
+    fn result(title: &str, description: &str, engine_ranks: &[(&str, u32)]) -> SearchResult {
+        let engines: Vec<&str> = engine_ranks.iter().map(|(engine, _)| *engine).collect();
+        let mut result = SearchResult::new(title, "https://example.com/", description, &engines);
+        for (engine, rank) in engine_ranks {
+            result.set_engine_rank(engine, *rank);
+        }
+        result
+    }
+
+    /// Returns the titles of the results in their order.
+    fn titles(results: &[SearchResult]) -> Vec<&str> {
+        results.iter().map(|result| result.title.as_str()).collect()
+    }
+
+    #[test]
+    fn bm25_ranks_the_better_match_first() {
+        let mut results = vec![
+            result("Cooking pasta", "Recipes for the weekend", &[]),
+            result("Rust news", "The weekly newsletter", &[]),
+            result("Rust programming", "Programming in rust", &[]),
+        ];
+
+        rank("rust programming", &mut results, &HashMap::new());
+
+        assert_eq!(
+            titles(&results),
+            ["Rust programming", "Rust news", "Cooking pasta"]
+        );
+        assert!(results[0].relevance_score > results[1].relevance_score);
+        assert_eq!(results[2].relevance_score, 0.0);
+    }
+
+    #[test]
+    fn bm25_weights_the_title_above_the_description() {
+        let mut results = vec![
+            result("A tutorial", "Learn rust", &[]),
+            result("Learn rust", "A tutorial", &[]),
+        ];
+
+        rank("rust", &mut results, &HashMap::new());
+
+        assert_eq!(titles(&results), ["Learn rust", "A tutorial"]);
+    }
+
+    #[test]
+    fn a_result_of_several_engines_ranks_above_a_single_engine_result() {
+        let mut results = vec![
+            result("single", "rust", &[("bing", 1)]),
+            result("agreed", "rust", &[("bing", 2), ("brave", 2)]),
+        ];
+
+        rank("rust", &mut results, &HashMap::new());
+
+        assert_eq!(titles(&results), ["agreed", "single"]);
+    }
+
+    #[test]
+    fn rank_fusion_follows_the_upstream_positions() {
+        let mut results = vec![
+            result("third", "", &[("bing", 3)]),
+            result("first", "", &[("bing", 1)]),
+            result("second", "", &[("bing", 2)]),
+        ];
+
+        rank("rust", &mut results, &HashMap::new());
+
+        assert_eq!(titles(&results), ["first", "second", "third"]);
+        // the fusion scores 1 / (60 + rank) are normalized by the best one.
+        for (result, position) in results.iter().zip(1..) {
+            let expected = FUSION_WEIGHT * (RRF_K + 1.0) / (RRF_K + position as f32);
+            assert!((result.relevance_score - expected).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn rank_fusion_lifts_a_result_of_several_engines() {
+        // 2 / (60 + 3) is larger than 1 / (60 + 1).
+        let mut results = vec![
+            result("single", "", &[("bing", 1)]),
+            result("several", "", &[("bing", 3), ("brave", 3)]),
+        ];
+
+        rank("rust", &mut results, &HashMap::new());
+
+        assert_eq!(titles(&results), ["several", "single"]);
+    }
+
+    #[test]
+    fn engine_weights_move_the_results_of_an_engine_up() {
+        let ranked = |engine_weights: &HashMap<String, f32>| {
+            let mut results = vec![
+                result("bing", "", &[("bing", 1)]),
+                result("brave", "", &[("brave", 2)]),
+            ];
+            rank("rust", &mut results, engine_weights);
+            titles(&results).join(",")
+        };
+
+        assert_eq!(ranked(&HashMap::new()), "bing,brave");
+        assert_eq!(
+            ranked(&HashMap::from([("brave".to_owned(), 1.5)])),
+            "brave,bing"
+        );
+        assert_eq!(
+            ranked(&HashMap::from([("bing".to_owned(), 0.5)])),
+            "brave,bing"
+        );
+        assert_eq!(
+            ranked(&HashMap::from([("Brave".to_owned(), 1.5)])),
+            "brave,bing"
+        );
+    }
+
+    #[test]
+    fn the_score_blends_the_text_relevance_and_the_rank_fusion() {
+        let mut results = vec![
+            result("rust", "", &[]),
+            result("unrelated", "", &[("brave", 1)]),
+            result("other", "", &[]),
+        ];
+
+        rank("rust", &mut results, &HashMap::new());
+
+        let scores: Vec<(&str, f32)> = results
+            .iter()
+            .map(|result| (result.title.as_str(), result.relevance_score))
+            .collect();
+        assert_eq!(
+            scores,
+            [
+                ("unrelated", FUSION_WEIGHT),
+                ("rust", TEXT_WEIGHT),
+                ("other", 0.0)
+            ]
+        );
+        assert_eq!(TEXT_WEIGHT + FUSION_WEIGHT, 1.0);
+    }
+}
@@ -0,0 +1,233 @@
+//! This module provides the functionality to canonicalize the urls of the search results so that
+//! the same page returned by different upstream engines in slightly different forms is recognized
+//! as a duplicate.
+
+use reqwest::Url;
+
+/// The query parameters which are only used for tracking and never change the page content.
+const TRACKING_PARAMETERS: [&str; 4] = ["fbclid", "gclid", "msclkid", "ref_src"];
+
+/// Returns whether the query parameter is a tracking parameter which should be ignored.
+///
+/// # Arguments
+///
+/// * `name` - It takes the (decoded) name of the query parameter.
+fn is_tracking_parameter(name: &str) -> bool {
+    name.to_ascii_lowercase().starts_with("utm_") || TRACKING_PARAMETERS.contains(&name)
+}
+
+/// Normalizes the percent-encoding of a url component. The escapes of the unreserved and the
+/// non-ascii characters are decoded, the escapes of the reserved characters (e.g. `%2F` or `%26`)
+/// are kept with an uppercase hex as decoding them would change the meaning of the url.
+///
+/// # Arguments
+///
+/// * `component` - It takes the path, or a name or value of a query parameter, of the url.
+fn normalize_escapes(component: &str) -> String {
+    let bytes = component.as_bytes();
+    let mut normalized = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+
+    while let Some(&byte) = bytes.get(index) {
+        let escaped = bytes
+            .get(index + 1..index + 3)
+            .filter(|hex| byte == b'%' && hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+
+        match escaped {
+            Some(decoded) if decoded.is_ascii_alphanumeric() || b"-._~".contains(&decoded) => {
+                normalized.push(decoded)
+            }
+            Some(decoded) if !decoded.is_ascii() => normalized.push(decoded),
+            Some(decoded) => normalized.extend(format!("%{decoded:02X}").bytes()),
+            None => {
+                normalized.push(byte);
+                index += 1;
+                continue;
+            }
+        }
+        index += 3;
+    }
+
+    String::from_utf8_lossy(&normalized).into_owned()
+}
+
+/// Builds the deduplication key for a result url. Two urls which point to the same page get the
+/// same key, even if they differ in:
+///
+/// * the scheme (`http://` vs `https://`),
+/// * a leading `www.` in the host or the case of the host,
+/// * a trailing slash in the path,
+/// * the fragment identifier,
+/// * `utm_*` and other tracking query parameters, or the order of the query parameters,
+/// * the percent-encoding of the unreserved and non-ascii characters (`%7E` vs `~`, `%c3%a9` vs
+///   `é`) or the case of the other escapes (`%2f` vs `%2F`), while `%2F` and `/` or `%26` and `&`
+///   stay distinct,
+/// * a `+` or a `%20` for a space in the query.
+///
+/// The key is not a valid url and is only meant to be compared. Urls which cannot be parsed are
+/// only trimmed and lowercased.
+///
+/// # Arguments
+///
+/// * `url` - It takes the url of the search result.
+pub fn canonical_url(url: &str) -> String {
+    let Ok(parsed) = Url::parse(url.trim()) else {
+        return url.trim().to_lowercase();
+    };
+
+    let host = parsed.host_str().unwrap_or_default();
+    let host = host.strip_prefix("www.").unwrap_or(host);
+
+    let path = normalize_escapes(parsed.path());
+    let path = path.trim_end_matches('/');
+
+    let mut query: Vec<(String, String)> = parsed
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let normalize = |part: &str| normalize_escapes(&part.replace('+', "%20"));
+            (normalize(name), normalize(value))
+        })
+        .filter(|(name, _)| !is_tracking_parameter(name))
+        .collect();
+    query.sort();
+
+    let mut key = match parsed.port() {
+        Some(port) => format!("{host}:{port}{path}"),
+        None => format!("{host}{path}"),
+    };
+    for (index, (name, value)) in query.iter().enumerate() {
+        key.push(if index == 0 { '?' } else { '&' });
+        key.push_str(name);
+        key.push('=');
+        key.push_str(value);
+    }
+
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collapses_the_forms_of_the_same_page() {
+        let cases = [
+            ("http://example.com/page", "https://example.com/page"),
+            ("https://www.example.com/page", "https://example.com/page"),
+            ("https://EXAMPLE.com/page", "https://example.com/page"),
+            ("https://example.com/page/", "https://example.com/page"),
+            ("https://example.com/", "https://example.com"),
+            (
+                "https://example.com/page#section",
+                "https://example.com/page",
+            ),
+            (
+                "https://example.com/page?utm_source=feed&utm_medium=rss",
+                "https://example.com/page",
+            ),
+            (
+                "https://example.com/page?fbclid=abc",
+                "https://example.com/page",
+            ),
+            (
+                "https://example.com/page?gclid=abc",
+                "https://example.com/page",
+            ),
+            (
+                "https://example.com/page?id=1&utm_campaign=spring",
+                "https://example.com/page?id=1",
+            ),
+            (
+                "https://example.com/page?b=2&a=1",
+                "https://example.com/page?a=1&b=2",
+            ),
+            ("https://example.com/%7Euser", "https://example.com/~user"),
+            ("https://example.com/caf%C3%A9", "https://example.com/café"),
+            (
+                "https://example.com/caf%c3%a9",
+                "https://example.com/caf%C3%A9",
+            ),
+            (
+                "https://example.com/search?q=caf%C3%A9",
+                "https://example.com/search?q=café",
+            ),
+            ("https://example.com/a%2fb", "https://example.com/a%2Fb"),
+            (
+                "https://example.com/search?q=a+b",
+                "https://example.com/search?q=a%20b",
+            ),
+            (
+                "  http://www.example.com/page/?utm_source=x#top ",
+                "https://example.com/page",
+            ),
+        ];
+
+        for (url, same_page) in cases {
+            assert_eq!(canonical_url(url), canonical_url(same_page), "{url}");
+        }
+    }
+
+    #[test]
+    fn keeps_the_parts_which_identify_a_page() {
+        let cases = [
+            (
+                "https://example.com/page?id=1",
+                "https://example.com/page?id=2",
+            ),
+            ("https://example.com/page?id=1", "https://example.com/page"),
+            (
+                "https://example.com/page?q=rust",
+                "https://example.com/page?query=rust",
+            ),
+            ("https://example.com/page", "https://example.com/other"),
+            ("https://example.com/page", "https://example.org/page"),
+            ("https://example.com/page", "https://docs.example.com/page"),
+            ("https://example.com:8443/page", "https://example.com/page"),
+        ];
+
+        for (url, other_page) in cases {
+            assert_ne!(canonical_url(url), canonical_url(other_page), "{url}");
+        }
+    }
+
+    #[test]
+    fn keeps_the_reserved_characters_encoded() {
+        let cases = [
+            ("https://example.com/a%2Fb", "https://example.com/a/b"),
+            (
+                "https://example.com/search?q=a%26b",
+                "https://example.com/search?q=a&b",
+            ),
+            (
+                "https://example.com/search?q=a%3Db",
+                "https://example.com/search?q=a=b",
+            ),
+            (
+                "https://example.com/search?q=a%2Bb",
+                "https://example.com/search?q=a+b",
+            ),
+        ];
+
+        for (url, other_page) in cases {
+            assert_ne!(canonical_url(url), canonical_url(other_page), "{url}");
+        }
+    }
+
+    #[test]
+    fn keeps_the_meaningful_query_parameters() {
+        assert_eq!(
+            canonical_url("https://example.com/watch?v=abc&utm_source=x&t=42"),
+            "example.com/watch?t=42&v=abc"
+        );
+    }
+
+    #[test]
+    fn only_trims_and_lowercases_an_invalid_url() {
+        assert_eq!(canonical_url(" Not A URL "), "not a url");
+    }
+}
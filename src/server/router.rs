@@ -4,7 +4,11 @@
 
 use crate::{
     config::Config,
+    engines::EngineRegistry,
     handler::{file_path, FileType},
+    health::EngineHealth,
+    metrics::Metrics,
+    models::engine_models::EngineHandler,
 };
 use actix_web::{get, http::header::ContentType, web, HttpRequest, HttpResponse};
 use tokio::fs::read_to_string;
@@ -35,6 +39,19 @@ pub async fn robots_data(_req: HttpRequest) -> Result<HttpResponse, Box<dyn std:
         .body(page_content))
 }
 
+/// Handles the route of the opensearch description document of the `crabbysearch` meta search
+/// engine website which allows browsers to add the instance as a search engine.
+#[get("/opensearch.xml")]
+pub async fn opensearch(
+    req: HttpRequest,
+    config: web::Data<Config>,
+) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let base_url = config.base_url(&req.connection_info());
+    Ok(HttpResponse::Ok()
+        .content_type("application/opensearchdescription+xml; charset=utf-8")
+        .body(crate::templates::views::opensearch::opensearch(&base_url).0))
+}
+
 /// Handles the route of about page of the `crabbysearch` meta search engine website.
 #[get("/about")]
 pub async fn about() -> Result<HttpResponse, Box<dyn std::error::Error>> {
@@ -47,16 +64,54 @@ pub async fn about() -> Result<HttpResponse, Box<dyn std::error::Error>> {
 #[get("/settings")]
 pub async fn settings(
     config: web::Data<Config>,
+    health: web::Data<EngineHealth>,
+    registry: web::Data<EngineRegistry>,
 ) -> Result<HttpResponse, Box<dyn std::error::Error>> {
-    Ok(HttpResponse::Ok().content_type(ContentType::html()).body(
-        crate::templates::views::settings::settings(
-            &config
+    let engines: Vec<(&EngineHandler, bool, bool)> = registry
+        .engines()
+        .map(|engine| {
+            let selected = config
                 .upstream_search_engines
-                .list()
                 .iter()
-                .map(|n| (*n, true))
-                .collect(),
-        )?
-        .0,
-    ))
+                .any(|name| name.eq_ignore_ascii_case(engine.name()));
+            (engine, selected, health.is_disabled(engine.name()))
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(crate::templates::views::settings::settings(&engines)?.0))
+}
+
+/// Handles the health route of the `crabbysearch` meta search engine website, which returns the
+/// liveness of the server and the health of every registered upstream engine as json.
+#[get("/health")]
+pub async fn health_check(
+    health: web::Data<EngineHealth>,
+    registry: web::Data<EngineRegistry>,
+) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    Ok(HttpResponse::Ok().json(health.report(registry.names())))
+}
+
+/// Handles the route of status page of the `crabbysearch` meta search engine website, which shows
+/// the health of every registered upstream engine.
+#[get("/status")]
+pub async fn status(
+    health: web::Data<EngineHealth>,
+    registry: web::Data<EngineRegistry>,
+) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(crate::templates::views::status::status(&health.report(registry.names())).0))
+}
+
+/// Handles the metrics route of the `crabbysearch` meta search engine website, which returns the
+/// metrics of the server and the upstream engines in the prometheus text format.
+#[get("/metrics")]
+pub async fn prometheus_metrics(
+    metrics: web::Data<Metrics>,
+) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4; charset=utf-8")
+        .body(metrics.encode()))
 }
@@ -3,19 +3,38 @@
 use crate::{
     cache::Cache,
     config::Config,
-    engines::Engines,
+    engines::EngineRegistry,
+    health::EngineHealth,
+    metrics::Metrics,
     models::{
-        aggregation_models::SearchResults, engine_models::EngineHandler,
-        server_models::SearchParams,
+        aggregation_models::SearchResults,
+        server_models::{OutputFormat, SearchParams, SearchResponse},
     },
-    results::aggregator::aggregate,
+    results::{
+        aggregator::aggregate,
+        filter::{filter_results, is_query_blocked},
+    },
+};
+use actix_web::{
+    get,
+    http::header::{self, ContentType},
+    web, HttpMessage, HttpRequest, HttpResponse,
 };
-use actix_web::{get, http::header::ContentType, web, HttpRequest, HttpResponse};
+use reqwest::Url;
+use std::collections::BTreeMap;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
 use tokio::join;
 
 /// Handles the route of search page of the `crabbysearch` meta search engine website and it takes
 /// two search url parameters `q` and `page` where `page` parameter is optional.
 ///
+/// With the `safesearch` url parameter set to 3 the results matching the blocklist (and not the
+/// allowlist) are filtered out, with it set to 4 queries matching the blocklist are disallowed.
+///
+/// The results are rendered as the html search page by default. They can be requested as json
+/// either with the `format=json` search url parameter or with an `Accept: application/json`
+/// request header, or as a feed with the `format=rss` and `format=atom` search url parameters.
+///
 /// # Example
 ///
 /// ```bash
@@ -27,11 +46,26 @@ use tokio::join;
 /// ```bash
 /// wget "http://127.0.0.1:8080/search?q=sweden"
 /// ```
+///
+/// Or
+///
+/// ```bash
+/// wget "http://127.0.0.1:8080/search?q=sweden&format=json"
+/// ```
+///
+/// Or as a rss/atom feed
+///
+/// ```bash
+/// wget "http://127.0.0.1:8080/search?q=sweden&format=rss"
+/// ```
 #[get("/search")]
 pub async fn search(
     req: HttpRequest,
     config: web::Data<Config>,
     cache: web::Data<Cache>,
+    health: web::Data<EngineHealth>,
+    metrics: web::Data<Metrics>,
+    registry: web::Data<EngineRegistry>,
 ) -> Result<HttpResponse, Box<dyn std::error::Error>> {
     let params = web::Query::<SearchParams>::from_query(req.query_string())?;
 
@@ -43,66 +77,222 @@ pub async fn search(
 
     let query = params.q.as_ref().unwrap().trim();
 
-    let cookie = req.cookie("appCookie");
+    // .max(1) makes sure that the page >= 0.
+    let page = params.page.unwrap_or(1).max(1) - 1;
 
-    log::info!("{cookie:?}");
+    // levels above 4 are treated as the strictest level.
+    let safe_search_level = params.safesearch.unwrap_or_default().min(4);
 
-    // Get search settings using the user's cookie or from the server's config
-    let search_settings: crate::engines::Engines = cookie
-        .and_then(|cookie_value| serde_json::from_str(&cookie_value.value().to_lowercase()).ok())
-        .unwrap_or_default();
+    // queries matching the blocklist are rejected outright on the strictest safe search level.
+    if safe_search_level >= 4 && is_query_blocked(query) {
+        let mut results = SearchResults::default();
+        results.set_disallowed();
+        results.set_safe_search_level(safe_search_level);
+        return render_results(&req, &config, &params, query, page, &results);
+    }
+
+    let search_settings = search_settings(&req, &config);
 
     // Closure wrapping the results function capturing local references
-    let get_results = |page| results(config.clone(), cache.clone(), query, page, &search_settings);
+    let get_results = |results_page| {
+        results(
+            config.clone(),
+            cache.clone(),
+            &health,
+            &metrics,
+            &registry,
+            query,
+            results_page,
+            results_page == page,
+            safe_search_level,
+            &search_settings,
+        )
+    };
 
-    // .max(1) makes sure that the page >= 0.
-    let page = params.page.unwrap_or(1).max(1) - 1;
     let previous_page = page.saturating_sub(1);
     let next_page = page + 1;
 
-    let mut results = (SearchResults::default(), String::default());
-    if page != previous_page {
+    // the adjacent pages are fetched along with the current page, so that they are cached.
+    let mut results = if page != previous_page {
         let (previous_results, current_results, next_results) = join!(
             get_results(previous_page),
             get_results(page),
             get_results(next_page)
         );
-        let (parsed_previous_results, parsed_next_results) = (previous_results?, next_results?);
-
-        let (cache_keys, results_list) = (
-            [
-                parsed_previous_results.1,
-                results.1.clone(),
-                parsed_next_results.1,
-            ],
-            [
-                parsed_previous_results.0,
-                results.0.clone(),
-                parsed_next_results.0,
-            ],
-        );
+        previous_results?;
+        next_results?;
+        current_results?
+    } else {
+        let (current_results, next_results) = join!(get_results(page), get_results(next_page));
+        next_results?;
+        current_results?
+    };
 
-        results = current_results?;
+    metrics.observe_results(results.0.results.len());
 
-        cache.cache_results(&results_list, &cache_keys);
-    } else {
-        let (current_results, next_results) = join!(get_results(page), get_results(page + 1));
+    // the results are cached unfiltered, so the filter lists are only applied to the current page.
+    if safe_search_level >= 3 {
+        let filtered = filter_results(&mut results.0.results);
+        results.0.set_filtered(filtered);
+    }
 
-        let parsed_next_results = next_results?;
+    render_results(&req, &config, &params, query, page, &results.0)
+}
 
-        results = current_results?;
+/// Renders the search results of the current page in the output format requested by the user.
+///
+/// # Arguments
+///
+/// * `req` - It takes the `HttpRequest` struct as a value.
+/// * `config` - It takes the parsed config struct.
+/// * `params` - It takes the parsed search url parameters.
+/// * `query` - It takes the current search query provided by the user.
+/// * `page` - It takes the current page number (starting from 0).
+/// * `search_results` - It takes the aggregated search results of the current page.
+///
+/// # Error
+///
+/// It returns an error if the results could not be rendered in the requested format.
+fn render_results(
+    req: &HttpRequest,
+    config: &Config,
+    params: &SearchParams,
+    query: &str,
+    page: u32,
+    search_results: &SearchResults,
+) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    match output_format(req, params) {
+        OutputFormat::Json => Ok(HttpResponse::Ok().json(SearchResponse {
+            query,
+            page: page + 1,
+            previous_page: (page != 0).then_some(page),
+            next_page: page + 2,
+            search_results,
+        })),
+        OutputFormat::Rss => Ok(HttpResponse::Ok()
+            .content_type("application/rss+xml; charset=utf-8")
+            .body(
+                crate::templates::views::feed::rss(
+                    query,
+                    &search_page_url(req, config, query)?,
+                    search_results,
+                )
+                .0,
+            )),
+        OutputFormat::Atom => {
+            let feed_url = format!("{}{}", config.base_url(&req.connection_info()), req.uri());
+            let updated = OffsetDateTime::now_utc().format(&Rfc3339)?;
 
-        let (cache_keys, results_list) = (
-            [results.1.clone(), parsed_next_results.1.clone()],
-            [results.0.clone(), parsed_next_results.0],
+            Ok(HttpResponse::Ok()
+                .content_type("application/atom+xml; charset=utf-8")
+                .body(
+                    crate::templates::views::feed::atom(
+                        query,
+                        &search_page_url(req, config, query)?,
+                        &feed_url,
+                        &updated,
+                        search_results,
+                    )
+                    .0,
+                ))
+        }
+        OutputFormat::Html => Ok(HttpResponse::Ok()
+            .content_type(ContentType::html())
+            .body(crate::templates::views::search::search(query, search_results).0)),
+    }
+}
+
+/// Builds the absolute url of the html search page for the given query on this instance, based on
+/// the public url of the instance if one is configured.
+///
+/// # Arguments
+///
+/// * `req` - It takes the `HttpRequest` struct as a value.
+/// * `config` - It takes the parsed config struct.
+/// * `query` - It takes the current search query provided by the user.
+///
+/// # Error
+///
+/// It returns an error if the host provided by the client does not form a valid url.
+fn search_page_url(
+    req: &HttpRequest,
+    config: &Config,
+    query: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let url = Url::parse_with_params(
+        &format!("{}/search", config.base_url(&req.connection_info())),
+        &[("q", query)],
+    )?;
+    Ok(url.into())
+}
+
+/// Gets the names of the user selected upstream search engines from the user's cookie, falling
+/// back to the engines selected in the config file if the cookie is missing or cannot be parsed.
+///
+/// The cookie stores a json object mapping the engine names to whether they are selected. The names
+/// are kept as they are, the registry matches them case-insensitively.
+///
+/// # Arguments
+///
+/// * `req` - It takes the `HttpRequest` struct as a value.
+/// * `config` - It takes the parsed config struct.
+pub(crate) fn search_settings(req: &HttpRequest, config: &Config) -> Vec<String> {
+    let cookie = req.cookie("appCookie");
+
+    log::info!("{cookie:?}");
+
+    cookie
+        .and_then(|cookie_value| {
+            serde_json::from_str::<BTreeMap<String, bool>>(cookie_value.value()).ok()
+        })
+        .map(|engines| {
+            engines
+                .into_iter()
+                .filter_map(|(name, selected)| selected.then_some(name))
+                .collect()
+        })
+        .unwrap_or_else(|| config.upstream_search_engines.clone())
+}
+
+/// Selects the output format of the search route. The `format` search url parameter takes
+/// precedence, otherwise json is chosen if the client prefers it over html through the `Accept`
+/// header and html is used as the fallback.
+///
+/// The media ranges of the `Accept` header are ranked by their quality and their specificity,
+/// ranges of equal rank keep the order in which the client listed them and ranges with a quality
+/// of zero are not acceptable. The first ranked range which is either json or html (including
+/// `text/*` and `*/*`) selects the format, so `Accept: text/html, application/json;q=0.1` is
+/// answered with html.
+///
+/// # Arguments
+///
+/// * `req` - It takes the `HttpRequest` struct as a value.
+/// * `params` - It takes the parsed search url parameters.
+fn output_format(req: &HttpRequest, params: &SearchParams) -> OutputFormat {
+    params.format.unwrap_or_else(|| {
+        let Some(accept) = req.get_header::<header::Accept>() else {
+            return OutputFormat::Html;
+        };
+        let acceptable = header::Accept(
+            accept
+                .iter()
+                .filter(|range| range.quality > header::Quality::ZERO)
+                .cloned()
+                .collect(),
         );
 
-        cache.cache_results(&results_list, &cache_keys);
-    }
+        let preferred = acceptable.ranked().into_iter().find_map(|range| {
+            match (range.type_().as_str(), range.subtype().as_str()) {
+                ("application", "json") => Some(OutputFormat::Json),
+                ("text", "html") | ("application", "xhtml+xml") | ("text" | "*", "*") => {
+                    Some(OutputFormat::Html)
+                }
+                _ => None,
+            }
+        });
 
-    Ok(HttpResponse::Ok()
-        .content_type(ContentType::html())
-        .body(crate::templates::views::search::search(query, &results.0).0))
+        preferred.unwrap_or(OutputFormat::Html)
+    })
 }
 
 /// Fetches the results for a query and page. It First checks the redis cache, if that
@@ -110,52 +300,96 @@ pub async fn search(
 ///
 /// # Arguments
 ///
-/// * `url` - It takes the url of the current page that requested the search results for a
-///     particular search query.
 /// * `config` - It takes a parsed config struct.
-/// * `query` - It takes the page number as u32 value.
-/// * `req` - It takes the `HttpRequest` struct as a value.
+/// * `cache` - It takes the cache of the search results.
+/// * `health` - It takes the health state of the upstream search engines.
+/// * `metrics` - It takes the metrics in which the cache hits and misses are recorded.
+/// * `registry` - It takes the registry of the upstream search engines.
+/// * `query` - It takes the current search query provided by the user.
+/// * `page` - It takes the page number as u32 value.
+/// * `requested` - It takes whether the page was requested by the user, rather than prefetched
+///   along with it, only the outcomes of the requested page are recorded in the engine health.
+/// * `safe_search_level` - It takes the safe search level which is passed on to the upstream
+///   search engines and is part of the cache key.
+/// * `upstream` - It takes the names of the upstream search engines selected by the user. Unknown
+///   engine names are reported in the engine errors of the results.
 ///
 /// # Error
 ///
 /// It returns the `SearchResults` struct if the search results could be successfully fetched from
 /// the cache or from the upstream search engines otherwise it returns an appropriate error.
+#[allow(clippy::too_many_arguments)]
 async fn results(
     config: web::Data<Config>,
     cache: web::Data<crate::cache::Cache>,
+    health: &EngineHealth,
+    metrics: &Metrics,
+    registry: &EngineRegistry,
     query: &str,
     page: u32,
-    upstream: &Engines,
+    requested: bool,
+    safe_search_level: u8,
+    upstream: &[String],
 ) -> Result<(SearchResults, String), Box<dyn std::error::Error>> {
-    // eagerly parse cookie value to evaluate safe search level
-
-    let cache_key = format!("search?q={}&page={}&engines={:?}", query, page, upstream);
+    let cache_key = format!(
+        "search?q={}&page={}&safesearch={}&engines={:?}",
+        query, page, safe_search_level, upstream
+    );
 
     // fetch the cached results json.
     let response = cache.cached_results(&cache_key);
+    metrics.observe_cache(response.is_some());
 
     if let Some(results) = response {
         return Ok((results, cache_key));
     }
 
-    // check if the cookie value is empty or not if it is empty then use the
-    // default selected upstream search engines from the config file otherwise
-    // parse the non-empty cookie and grab the user selected engines from the
-    // UI and use that.
-    let mut results: SearchResults = match false {
-        false => aggregate(query, page, config, &Vec::<EngineHandler>::from(upstream)).await?,
+    let (engines, unknown_engines) = registry.select(upstream);
+
+    let (mut results, late_results) = match engines.is_empty() {
+        false => {
+            let aggregation = aggregate(
+                query,
+                page,
+                safe_search_level,
+                config,
+                health,
+                requested,
+                metrics,
+                &engines,
+            )
+            .await?;
+            (aggregation.results, aggregation.late_results)
+        }
         true => {
             let mut search_results = SearchResults::default();
             search_results.set_no_engines_selected();
-            search_results
+            (search_results, None)
         }
     };
-    let (engine_errors_info, results_empty_check, no_engines_selected) = (
-        results.engine_errors_info().is_empty(),
-        results.results().is_empty(),
-        results.no_engines_selected(),
+    results
+        .engine_errors_info
+        .extend(unknown_engines.iter().cloned());
+    results.set_safe_search_level(safe_search_level);
+    cache.cache_results(
+        std::slice::from_ref(&results),
+        std::slice::from_ref(&cache_key),
     );
-    results.set_filtered(engine_errors_info & results_empty_check & !no_engines_selected);
-    cache.cache_results(&[results.clone()], &[cache_key.clone()]);
+
+    // the results of the late engines replace the cached results once all engines answered.
+    if let Some(late_results) = late_results {
+        let cache_key = cache_key.clone();
+        actix_web::rt::spawn(async move {
+            if let Ok(mut complete_results) = late_results.await {
+                complete_results.engine_errors_info.extend(unknown_engines);
+                complete_results.set_safe_search_level(safe_search_level);
+                cache.cache_results(
+                    std::slice::from_ref(&complete_results),
+                    std::slice::from_ref(&cache_key),
+                );
+            }
+        });
+    }
+
     Ok((results, cache_key))
 }
@@ -0,0 +1,52 @@
+//! This module handles the search suggestions route of the search engine website.
+
+use crate::{
+    cache::Cache, config::Config, engines::EngineRegistry, models::server_models::SuggestParams,
+    results::aggregator::aggregate_suggestions,
+};
+use actix_web::{get, web, HttpRequest, HttpResponse};
+
+use super::search::search_settings;
+
+/// Handles the route of the search suggestions of the `crabbysearch` meta search engine website.
+/// It takes the partial query as the `q` url parameter and returns the suggestions aggregated from
+/// the user selected upstream search engines in the opensearch suggestions json format, e.g.
+/// `["swe", ["sweden", "sweden population"]]`.
+///
+/// # Example
+///
+/// ```bash
+/// wget "http://127.0.0.1:8080/suggest?q=swe"
+/// ```
+#[get("/suggest")]
+pub async fn suggest(
+    req: HttpRequest,
+    config: web::Data<Config>,
+    cache: web::Data<Cache>,
+    registry: web::Data<EngineRegistry>,
+) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let params = web::Query::<SuggestParams>::from_query(req.query_string())?;
+    let query = params.q.as_deref().unwrap_or_default().trim();
+
+    let suggestions = match query.is_empty() {
+        true => Vec::new(),
+        false => {
+            let upstream = search_settings(&req, &config);
+            let cache_key = format!("suggest?q={}&engines={:?}", query, upstream);
+
+            match cache.cached_suggestions(&cache_key) {
+                Some(suggestions) => suggestions,
+                None => {
+                    let (engines, _) = registry.select(&upstream);
+                    let suggestions = aggregate_suggestions(query, &config, &engines).await;
+                    cache.cache_suggestions(&cache_key, &suggestions);
+                    suggestions
+                }
+            }
+        }
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/x-suggestions+json")
+        .body(serde_json::to_string(&(query, suggestions))?))
+}
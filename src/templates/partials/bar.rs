@@ -15,7 +15,8 @@ use maud::{html, Markup, PreEscaped};
 pub fn bar(query: &str) -> Markup {
     html!(
         (PreEscaped("<div class=\"search_bar\">"))
-            input type="search" name="search-box" value=(query) placeholder="Type to search";
+            input type="search" name="search-box" value=(query) placeholder="Type to search" list="search-suggestions" autocomplete="off";
+            datalist id="search-suggestions" {}
             button type="submit" onclick="searchWeb()" {
                 img src="./images/magnifying_glass.svg" alt="Info icon for error box";
             }
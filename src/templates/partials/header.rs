@@ -24,6 +24,7 @@ pub fn header() -> Markup {
             meta name="viewport" content="width=device-width, initial-scale=1";
             link href=("static/colorschemes/monokai.css") rel="stylesheet" type="text/css";
             link href=("static/themes/simple.css") rel="stylesheet" type="text/css";
+            link href="/opensearch.xml" rel="search" type="application/opensearchdescription+xml" title="crabbysearch";
         }
 
         (PreEscaped("<body onload=\"getClientSettings()\">"))
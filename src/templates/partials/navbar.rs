@@ -13,6 +13,7 @@ pub fn navbar() -> Markup {
             ul{
                li{a href="about"{"about"}}
                li{a href="settings"{"settings"}}
+               li{a href="status"{"status"}}
             }
         }
     )
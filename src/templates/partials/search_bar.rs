@@ -1,19 +1,29 @@
 //! A module that handles `search bar` partial for the search page in the `crabbysearch` frontend.
 
+use std::collections::BTreeMap;
+
 use maud::{html, Markup, PreEscaped};
 
 use crate::{models::aggregation_models::EngineErrorInfo, templates::partials::bar::bar};
 
-/// A constant holding the named safe search level options for the corresponding values 0, 1 and 2.
+/// A constant holding the named safe search level options for the corresponding values 0, 1 and 2
+/// which can be selected by the user.
 const SAFE_SEARCH_LEVELS_NAME: [&str; 3] = ["None", "Low", "Moderate"];
 
+/// A constant holding the names of the safe search levels 3 and 4, which filter the results and
+/// the queries with the blocklist. They can only be set through the `safesearch` url parameter and
+/// are shown in the disabled dropdown.
+const FILTER_LEVELS_NAME: [&str; 2] = ["Filtered", "Blocked"];
+
 /// A functions that handles the html code for the search bar for the search page.
 ///
 /// # Arguments
 ///
 /// * `engine_errors_info` - It takes the engine errors list containing errors for each upstream
-///     search engine which failed to provide results as an argument.
-/// * `safe_search_level` - It takes the safe search level with values from 0-2 as an argument.
+///   search engine which failed to provide results as an argument.
+/// * `engine_response_times` - It takes the time (millisecs) each upstream search engine took to
+///   answer as an argument.
+/// * `safe_search_level` - It takes the safe search level with values from 0-4 as an argument.
 /// * `query` - It takes the current search query provided by user as an argument.
 ///
 /// # Returns
@@ -21,6 +31,7 @@ const SAFE_SEARCH_LEVELS_NAME: [&str; 3] = ["None", "Low", "Moderate"];
 /// It returns the compiled html code for the search bar as a result.
 pub fn search_bar(
     engine_errors_info: &[EngineErrorInfo],
+    engine_response_times: &BTreeMap<String, u64>,
     safe_search_level: u8,
     query: &str,
 ) -> Markup {
@@ -36,10 +47,11 @@ pub fn search_bar(
                          @for errors in engine_errors_info{
                             .error_item{
                                span class="engine_name"{(errors.engine)}
-                               span class="engine_name"{(errors.error)}
-                               span class="severity_color" style="background: {{{this.severity_color}}};"{}
+                               span class="engine_name" title=(errors.message){(errors.error)}
+                               span class="severity_color" style=(format!("background: {};", errors.severity_color)){}
                             }
                          }
+                         (response_times(engine_response_times))
                       }
                    }
                    @else {
@@ -50,6 +62,7 @@ pub fn search_bar(
                          .no_errors{
                             "Everything looks good 🙂!!"
                          }
+                         (response_times(engine_response_times))
                       }
                   }
                 }
@@ -69,8 +82,33 @@ pub fn search_bar(
                        option value=(idx) {(format!("SafeSearch: {name}"))}
                    }
                }
+               @if let Some(name) = FILTER_LEVELS_NAME.get((safe_search_level as usize).wrapping_sub(3)) {
+                   option value=(safe_search_level) selected {(format!("SafeSearch: {name}"))}
+               }
                (PreEscaped("</select>"))
             }
         }
     )
 }
+
+/// A function that handles the html code for the time each upstream engine took to answer, which
+/// is listed in the error box.
+///
+/// # Arguments
+///
+/// * `engine_response_times` - It takes the time (millisecs) each upstream search engine took to
+///   answer as an argument.
+///
+/// # Returns
+///
+/// It returns the compiled html code for the response times as a result.
+fn response_times(engine_response_times: &BTreeMap<String, u64>) -> Markup {
+    html!(
+        @for (engine, millis) in engine_response_times {
+            .engine_time{
+               span class="engine_name"{(engine)}
+               span{(millis) " ms"}
+            }
+        }
+    )
+}
@@ -2,17 +2,20 @@
 
 use maud::{html, Markup};
 
+use crate::models::engine_models::EngineHandler;
+
 /// A functions that handles the html code for the engines tab for the settings page for the search page.
 ///
 /// # Arguments
 ///
-/// * `engine_names` - It takes the key value pair list of all available engine names and there corresponding
-///     selected (enabled/disabled) value as an argument.
+/// * `engines` - It takes the list of all registered engines, there corresponding selected
+///   (enabled/disabled) value and whether they are temporarily disabled after repeated failures
+///   as an argument.
 ///
 /// # Returns
 ///
 /// It returns the compiled html markup code for the engines tab.
-pub fn engines(engine_names: &Vec<(&str, bool)>) -> Markup {
+pub fn engines(engines: &[(&EngineHandler, bool, bool)]) -> Markup {
     html!(
         div class="engines tab"{
            h1{"Engines"}
@@ -24,7 +27,7 @@ pub fn engines(engine_names: &Vec<(&str, bool)>) -> Markup {
                // Checks whether all the engines are selected or not if they are then the
                // checked `select_all` button is rendered otherwise the unchecked version
                // is rendered.
-               @if engine_names.iter().all(|selected| selected.1){
+               @if engines.iter().all(|selected| selected.1){
                    .toggle_btn{
                       label class="switch"{
                          input type="checkbox" class="select_all" onchange="toggleAllSelection()" checked;
@@ -43,26 +46,20 @@ pub fn engines(engine_names: &Vec<(&str, bool)>) -> Markup {
                    }
                }
                hr;
-               @for (engine_name, selected) in engine_names{
-                   // Checks whether the `engine_name` is selected or not if they are then the
-                   // checked `engine` button is rendered otherwise the unchecked version is
-                   // rendered.
-                   @if *selected {
-                       .toggle_btn{
-                           label class="switch"{
-                              input type="checkbox" class="engine" checked;
-                              span class="slider round"{}
-                           }
-                           (format!("{}{}",&engine_name[..1].to_uppercase(), &engine_name[1..]))
+               @for (engine, selected, disabled) in engines{
+                   // The engine name is stored on the checkbox as it is used as the key of the
+                   // engine in the settings cookie, while the display name is shown to the user.
+                   .toggle_btn{
+                       label class="switch"{
+                          input type="checkbox" class="engine" data-engine=(engine.name()) checked[*selected];
+                          span class="slider round"{}
                        }
-                   }
-                   @else {
-                       .toggle_btn {
-                           label class="switch"{
-                              input type="checkbox" class="engine";
-                              span class="slider round"{}
+                       (engine.info().display_name)
+                       span class="engine_categories"{(engine.info().categories.join(", "))}
+                       @if *disabled {
+                           span class="engine_disabled" title="The engine is skipped after repeated failures and is retried later"{
+                              "temporarily disabled"
                            }
-                           (format!("{}{}",&engine_name[..1], &engine_name[1..]))
                        }
                    }
                }
@@ -0,0 +1,105 @@
+//! A module that handles the rss and atom feed views for the search results in the `crabbysearch`
+//! frontend.
+
+use maud::{html, Markup, PreEscaped};
+use scraper::Html;
+
+use crate::models::aggregation_models::SearchResults;
+
+/// The xml declaration which is prepended to every xml document served by the frontend.
+pub const XML_DECLARATION: &str = r#"<?xml version="1.0" encoding="UTF-8"?>"#;
+
+/// Returns the text of a html fragment without its markup, the titles of the feed entries are
+/// plain text unlike the html titles of the search results.
+///
+/// # Arguments
+///
+/// * `fragment` - It takes the html fragment, such as the title of a search result.
+fn plain_text(fragment: &str) -> String {
+    Html::parse_fragment(fragment)
+        .root_element()
+        .text()
+        .collect()
+}
+
+/// A function that handles the xml code for the rss 2.0 feed of the search results.
+///
+/// # Arguments
+///
+/// * `query` - It takes the current search query provided by the user as an argument.
+/// * `search_page_url` - It takes the url of the html search page for the query as an argument.
+/// * `search_results` - It takes the aggregated search results as an argument.
+///
+/// # Returns
+///
+/// It returns the compiled xml markup code as a result.
+pub fn rss(query: &str, search_page_url: &str, search_results: &SearchResults) -> Markup {
+    html!(
+        (PreEscaped(XML_DECLARATION))
+        rss version="2.0" {
+            channel {
+                title {(format!("crabbysearch: {query}"))}
+                link {(search_page_url)}
+                description {(format!("Search results for '{query}' from crabbysearch"))}
+                @for result in search_results.results.iter() {
+                    item {
+                        title {(plain_text(&result.title))}
+                        link {(result.url)}
+                        description {(result.description)}
+                        guid isPermaLink="true" {(result.url)}
+                        @for name in &result.engine {
+                            category {(name)}
+                        }
+                    }
+                }
+            }
+        }
+    )
+}
+
+/// A function that handles the xml code for the atom feed of the search results.
+///
+/// # Arguments
+///
+/// * `query` - It takes the current search query provided by the user as an argument.
+/// * `search_page_url` - It takes the url of the html search page for the query as an argument.
+/// * `feed_url` - It takes the url of the feed itself as an argument.
+/// * `updated` - It takes the rfc3339 timestamp at which the feed was generated as an argument.
+/// * `search_results` - It takes the aggregated search results as an argument.
+///
+/// # Returns
+///
+/// It returns the compiled xml markup code as a result.
+pub fn atom(
+    query: &str,
+    search_page_url: &str,
+    feed_url: &str,
+    updated: &str,
+    search_results: &SearchResults,
+) -> Markup {
+    html!(
+        (PreEscaped(XML_DECLARATION))
+        feed xmlns="http://www.w3.org/2005/Atom" {
+            title {(format!("crabbysearch: {query}"))}
+            id {(feed_url)}
+            updated {(updated)}
+            link rel="self" href=(feed_url) {}
+            link rel="alternate" type="text/html" href=(search_page_url) {}
+            author {
+                name {"crabbysearch"}
+            }
+            @for result in search_results.results.iter() {
+                entry {
+                    title {(plain_text(&result.title))}
+                    id {(result.url)}
+                    updated {(updated)}
+                    link href=(result.url) {}
+                    summary type="html" {(result.description)}
+                    @for name in &result.engine {
+                        category term=(name) {}
+                    }
+                }
+            }
+        }
+    )
+}
@@ -2,7 +2,10 @@
 //! `crabbysearch` frontend.
 
 pub mod about;
+pub mod feed;
 pub mod index;
 pub mod not_found;
+pub mod opensearch;
 pub mod search;
 pub mod settings;
+pub mod status;
@@ -0,0 +1,31 @@
+//! A module that handles the view for the opensearch description document in the `crabbysearch`
+//! frontend.
+
+use maud::{html, Markup, PreEscaped};
+
+use super::feed::XML_DECLARATION;
+
+/// A function that handles the xml code for the opensearch description document which allows
+/// browsers to add the instance as a search engine and to autocomplete from it.
+///
+/// # Arguments
+///
+/// * `base_url` - It takes the public base url of the instance without a trailing slash as an
+///   argument.
+///
+/// # Returns
+///
+/// It returns the compiled xml markup code as a result.
+pub fn opensearch(base_url: &str) -> Markup {
+    html!(
+        (PreEscaped(XML_DECLARATION))
+        OpenSearchDescription xmlns="http://a9.com/-/spec/opensearch/1.1/" {
+            ShortName {"crabbysearch"}
+            Description {"crabbysearch: a simple meta search engine written in rust."}
+            InputEncoding {"UTF-8"}
+            Url type="text/html" method="get" template=(format!("{base_url}/search?q={{searchTerms}}")) {}
+            Url type="application/x-suggestions+json" method="get" template=(format!("{base_url}/suggest?q={{searchTerms}}")) {}
+            Url type="application/opensearchdescription+xml" rel="self" template=(format!("{base_url}/opensearch.xml")) {}
+        }
+    )
+}
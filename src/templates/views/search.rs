@@ -23,7 +23,7 @@ pub fn search(query: &str, search_results: &SearchResults) -> Markup {
     html!(
         (header())
         main class="results"{
-           (search_bar(&search_results.engine_errors_info, search_results.safe_search_level, query))
+           (search_bar(&search_results.engine_errors_info, &search_results.engine_response_times, search_results.safe_search_level, query))
            .results_aggregated{
               @if !search_results.results.is_empty() {
                   @for result in search_results.results.iter(){
@@ -2,10 +2,13 @@
 
 use maud::{html, Markup};
 
-use crate::templates::partials::{
-    footer::footer,
-    header::header,
-    settings_tabs::{cookies::cookies, engines::engines},
+use crate::{
+    models::engine_models::EngineHandler,
+    templates::partials::{
+        footer::footer,
+        header::header,
+        settings_tabs::{cookies::cookies, engines::engines},
+    },
 };
 
 /// A function that handles the html code for the settings page view in the search engine frontend.
@@ -16,14 +19,15 @@ use crate::templates::partials::{
 /// * `colorscheme` - It takes the colorscheme name as an argument.
 /// * `theme` - It takes the theme name as an argument.
 /// * `animation` - It takes the animation name as an argument.
-/// * `engine_names` - It takes a list of engine names as an argument.
+/// * `engines_list` - It takes the list of all registered engines, whether they are selected by
+///   default and whether they are temporarily disabled as an argument.
 ///
 /// # Error
 ///
 /// This function returns a compiled html markup code on success otherwise returns a standard error
 /// message.
 pub fn settings(
-    engine_names: &Vec<(&'static str, bool)>,
+    engines_list: &[(&EngineHandler, bool, bool)],
 ) -> Result<Markup, Box<dyn std::error::Error>> {
     Ok(html!(
         (header())
@@ -38,7 +42,7 @@ pub fn settings(
                   .btn onclick="setActiveTab(this)"{"cookies"}
               }
               .main_container{
-                  (engines(&engine_names))
+                  (engines(engines_list))
                   (cookies())
                   p class="message"{}
                   button type="submit" onclick="setClientSettings()"{"Save"}
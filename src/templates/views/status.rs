@@ -0,0 +1,64 @@
+//! A module that handles the view for the status page in the `crabbysearch` frontend.
+
+use maud::{html, Markup};
+
+use crate::{
+    models::health_models::HealthReport,
+    templates::partials::{footer::footer, header::header},
+};
+
+/// A function that handles the html code for the status page view in the search engine frontend.
+///
+/// # Arguments
+///
+/// * `report` - It takes the health of the server and of the upstream engines as an argument.
+///
+/// # Returns
+///
+/// It returns the compiled html markup code as a result.
+pub fn status(report: &HealthReport) -> Markup {
+    let millis =
+        |latency: Option<u64>| latency.map_or("-".to_owned(), |latency| format!("{latency} ms"));
+
+    html!(
+        (header())
+        main class="status-container"{
+           h1{"Status"}
+           p class="description"{
+              (format!(
+                  "Up for {} secs, the error rates and latencies are computed over the last {} secs.",
+                  report.uptime, report.window
+              ))
+           }
+           table{
+              thead{
+                 tr{
+                    th{"engine"}
+                    th{"state"}
+                    th{"last success"}
+                    th{"last error"}
+                    th{"requests"}
+                    th{"error rate"}
+                    th{"p50 latency"}
+                    th{"p95 latency"}
+                 }
+              }
+              tbody{
+                 @for (engine, status) in &report.engines {
+                    tr{
+                       td{(engine)}
+                       td class=(format!("engine_state {}", status.state.name())){(status.state.name())}
+                       td{(status.last_success.as_deref().unwrap_or("-"))}
+                       td title=[status.last_error_at.as_deref()]{(status.last_error.as_deref().unwrap_or("-"))}
+                       td{(status.requests)}
+                       td{(status.error_rate.map_or("-".to_owned(), |rate| format!("{:.0} %", rate * 100.0)))}
+                       td{(millis(status.p50_latency))}
+                       td{(millis(status.p95_latency))}
+                    }
+                 }
+              }
+           }
+        }
+        (footer())
+    )
+}
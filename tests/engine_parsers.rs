@@ -0,0 +1,386 @@
+//! Regression tests of the result page parsers of the upstream engines against the saved html and
+//! json fixtures in `tests/fixtures`, so that markup changes of an engine show up without a network
+//! connection. Every engine has a fixture with results and one without results.
+
+use std::collections::BTreeMap;
+
+use crabbysearch::{
+    config::{DeclarativeEngineConfig, HtmlEngineConfig, JsonEngineConfig},
+    engines::{
+        bing::Bing, brave::Brave, duckduckgo::DuckDuckGo, html_engine::HtmlEngine,
+        json_engine::JsonEngine, librex::LibreX, mojeek::Mojeek, searx::Searx,
+        startpage::Startpage,
+    },
+    models::{aggregation_models::SearchResult, engine_models::EngineError},
+};
+use error_stack::Result;
+use reqwest::Url;
+
+/// The title, url and description of the first results of the `rust programming` fixtures.
+const RUST_LANG: (&str, &str, &str) = (
+    "Rust Programming Language",
+    "https://www.rust-lang.org/",
+    "A language empowering everyone to build reliable and efficient software.",
+);
+
+/// Asserts that the parsed results match the expected titles, urls and descriptions in order and
+/// are attributed to the engine.
+fn assert_results(
+    results: Result<Vec<(String, SearchResult)>, EngineError>,
+    engine: &str,
+    expected: &[(&str, &str, &str)],
+) {
+    let results = results.unwrap();
+    let parsed: Vec<(&str, &str, &str)> = results
+        .iter()
+        .map(|(url, result)| {
+            assert_eq!(url, &result.url);
+            assert_eq!(result.engine, vec![engine.to_owned()]);
+            (
+                result.title.as_str(),
+                result.url.as_str(),
+                result.description.as_str(),
+            )
+        })
+        .collect();
+
+    assert_eq!(parsed, expected);
+}
+
+/// Asserts that the parser reported the expected kind of error.
+fn assert_error(results: Result<Vec<(String, SearchResult)>, EngineError>, kind: &str) {
+    let error = results.unwrap_err();
+    assert_eq!(error.current_context().kind(), kind, "{error:?}");
+}
+
+/// Asserts that the parser reported an empty result set.
+fn assert_no_results(results: Result<Vec<(String, SearchResult)>, EngineError>) {
+    assert_error(results, "EmptyResultSet");
+}
+
+#[test]
+fn bing() {
+    let engine = Bing::default();
+
+    assert_results(
+        engine.parse_results(include_str!("fixtures/bing/results.html")),
+        "bing",
+        &[
+            RUST_LANG,
+            (
+                "The Rust Programming Language - Learn Rust",
+                "https://doc.rust-lang.org/book/",
+                "The Rust Programming Language by Steve Klabnik and Carol Nichols.",
+            ),
+        ],
+    );
+    assert_no_results(engine.parse_results(include_str!("fixtures/bing/no_results.html")));
+}
+
+#[test]
+fn brave() {
+    let engine = Brave::default();
+
+    assert_results(
+        engine.parse_results(include_str!("fixtures/brave/results.html")),
+        "brave",
+        &[
+            RUST_LANG,
+            (
+                "Rust (programming language) - Wikipedia",
+                "https://en.wikipedia.org/wiki/Rust_(programming_language)",
+                "Rust is a general-purpose programming language emphasizing performance.",
+            ),
+        ],
+    );
+    assert_no_results(engine.parse_results(include_str!("fixtures/brave/no_results.html")));
+}
+
+#[test]
+fn duckduckgo() {
+    let engine = DuckDuckGo::default();
+
+    assert_results(
+        engine.parse_results(include_str!("fixtures/duckduckgo/results.html")),
+        "duckduckgo",
+        &[
+            (
+                "Rust Programming Language",
+                "https://www.rust-lang.org",
+                "A language empowering everyone to build reliable and efficient software.",
+            ),
+            (
+                "The Rust Programming Language - The Rust Programming Language",
+                "https://doc.rust-lang.org/book/",
+                "by Steve Klabnik and Carol Nichols, with contributions from the Rust Community.",
+            ),
+        ],
+    );
+    assert_no_results(engine.parse_results(include_str!("fixtures/duckduckgo/no_results.html")));
+}
+
+#[test]
+fn mojeek() {
+    let engine = Mojeek::default();
+
+    assert_results(
+        engine.parse_results(include_str!("fixtures/mojeek/results.html")),
+        "mojeek",
+        &[
+            RUST_LANG,
+            (
+                "The Rust Programming Language",
+                "https://doc.rust-lang.org/book/",
+                "by Steve Klabnik and Carol Nichols, with contributions from the Rust Community.",
+            ),
+        ],
+    );
+    assert_no_results(engine.parse_results(include_str!("fixtures/mojeek/no_results.html")));
+}
+
+#[test]
+fn startpage() {
+    let engine = Startpage::default();
+
+    assert_results(
+        engine.parse_results(include_str!("fixtures/startpage/results.html")),
+        "startpage",
+        &[
+            RUST_LANG,
+            (
+                "The Rust Programming Language",
+                "https://doc.rust-lang.org/book/",
+                "by Steve Klabnik and Carol Nichols, with contributions from the Rust Community.",
+            ),
+        ],
+    );
+    assert_no_results(engine.parse_results(include_str!("fixtures/startpage/no_results.html")));
+}
+
+#[test]
+fn librex() {
+    let engine = LibreX::default();
+
+    assert_results(
+        engine.parse_results(include_str!("fixtures/librex/results.html")),
+        "librex",
+        &[
+            RUST_LANG,
+            (
+                "The Rust Programming Language",
+                "https://doc.rust-lang.org/book/",
+                "by Steve Klabnik and Carol Nichols, with contributions from the Rust Community.",
+            ),
+        ],
+    );
+    assert_no_results(engine.parse_results(include_str!("fixtures/librex/no_results.html")));
+}
+
+#[test]
+fn searx_html() {
+    let engine = Searx::default();
+
+    assert_results(
+        engine.parse_html_results(include_str!("fixtures/searx/results.html")),
+        "searx",
+        &[
+            RUST_LANG,
+            (
+                "The Rust Programming Language",
+                "https://doc.rust-lang.org/book/",
+                "by Steve Klabnik and Carol Nichols, with contributions from the Rust Community.",
+            ),
+        ],
+    );
+    assert_no_results(engine.parse_html_results(include_str!("fixtures/searx/no_results.html")));
+}
+
+#[test]
+fn searx_json() {
+    let engine = Searx::default();
+
+    assert_results(
+        engine.parse_json_results(include_bytes!("fixtures/searx/results.json")),
+        "searx",
+        &[
+            RUST_LANG,
+            (
+                "The Rust Programming Language",
+                "https://doc.rust-lang.org/book/",
+                "",
+            ),
+        ],
+    );
+    assert_no_results(engine.parse_json_results(include_bytes!("fixtures/searx/no_results.json")));
+}
+
+#[test]
+fn searx_json_escapes_the_text_and_accepts_a_null_description() {
+    let engine = Searx::default();
+
+    assert_results(
+        engine.parse_json_results(include_bytes!("fixtures/searx/markup.json")),
+        "searx",
+        &[
+            (
+                "&lt;script&gt;alert(1)&lt;/script&gt; &amp; friends",
+                "https://example.com/xss",
+                "Pages about &lt;img src=x onerror=alert(1)&gt;",
+            ),
+            (
+                "A result without a description",
+                "https://example.com/null",
+                "",
+            ),
+        ],
+    );
+}
+
+#[test]
+fn captcha_pages() {
+    let captcha = include_str!("fixtures/captcha.html");
+
+    assert_error(Bing::default().parse_results(captcha), "Captcha");
+    assert_error(DuckDuckGo::default().parse_results(captcha), "Captcha");
+    assert_error(Searx::default().parse_html_results(captcha), "Captcha");
+}
+
+#[test]
+fn changed_markup() {
+    // the result page of another engine stands in for a page whose markup has changed.
+    let changed = include_str!("fixtures/startpage/results.html");
+
+    assert_error(Bing::default().parse_results(changed), "ParseFailure");
+    assert_error(Mojeek::default().parse_results(changed), "ParseFailure");
+    assert_error(LibreX::default().parse_results(changed), "ParseFailure");
+    assert_error(
+        Searx::default().parse_json_results(changed.as_bytes()),
+        "ParseFailure",
+    );
+}
+
+/// Builds the shared part of the config of a declarative engine.
+fn declarative_config() -> DeclarativeEngineConfig {
+    DeclarativeEngineConfig {
+        display_name: None,
+        categories: vec!["general".to_owned()],
+        url: "https://search.example.com/search?q={query}".to_owned(),
+        results_per_page: 10,
+        headers: BTreeMap::new(),
+        cookies: BTreeMap::new(),
+    }
+}
+
+/// Builds the declarative html engine parsing the `html_engine` fixtures.
+fn html_engine_of(engine: DeclarativeEngineConfig) -> HtmlEngine {
+    HtmlEngine::new(
+        "example",
+        &HtmlEngineConfig {
+            engine,
+            no_results: ".notice".to_owned(),
+            no_results_marker: Some("did not match any documents".to_owned()),
+            results: "#results>.result".to_owned(),
+            result_title: "a.title".to_owned(),
+            result_url: "a.title".to_owned(),
+            result_description: "p.snippet".to_owned(),
+        },
+    )
+    .unwrap()
+}
+
+#[test]
+fn html_engine() {
+    let engine = html_engine_of(declarative_config());
+    let base_url = Url::parse("https://search.example.com/search?q=rust").unwrap();
+
+    assert_results(
+        engine.parse_results(include_str!("fixtures/html_engine/results.html"), &base_url),
+        "example",
+        &[
+            (
+                "Rust (programming language)",
+                "https://search.example.com/wiki/Rust",
+                "A general-purpose programming language.",
+            ),
+            RUST_LANG,
+        ],
+    );
+    assert_no_results(engine.parse_results(
+        include_str!("fixtures/html_engine/no_results.html"),
+        &base_url,
+    ));
+}
+
+#[test]
+fn html_engine_escapes_the_text_and_drops_the_script_links() {
+    let engine = html_engine_of(declarative_config());
+    let base_url = Url::parse("https://search.example.com/search?q=xss").unwrap();
+
+    assert_results(
+        engine.parse_results(include_str!("fixtures/html_engine/markup.html"), &base_url),
+        "example",
+        &[(
+            "&lt;script&gt;alert(1)&lt;/script&gt; &amp; friends",
+            "https://search.example.com/wiki/XSS",
+            "Pages about &lt;img src=x onerror=alert(1)&gt; &quot;quoted&quot;",
+        )],
+    );
+}
+
+#[test]
+fn html_engine_rejects_an_invalid_url_template() {
+    let config = HtmlEngineConfig {
+        engine: DeclarativeEngineConfig {
+            url: "search.example.com/search?q={query}".to_owned(),
+            ..declarative_config()
+        },
+        no_results: ".notice".to_owned(),
+        no_results_marker: None,
+        results: "#results>.result".to_owned(),
+        result_title: "a.title".to_owned(),
+        result_url: "a.title".to_owned(),
+        result_description: "p.snippet".to_owned(),
+    };
+
+    assert!(HtmlEngine::new("example", &config).is_err());
+}
+
+#[test]
+fn json_engine() {
+    let engine = JsonEngine::new(
+        "example",
+        &JsonEngineConfig {
+            engine: declarative_config(),
+            results: "/data/items".to_owned(),
+            result_title: "/title".to_owned(),
+            result_url: "/links/html".to_owned(),
+            result_description: Some("/summary".to_owned()),
+        },
+    )
+    .unwrap();
+    let base_url = Url::parse("https://search.example.com/search?q=rust").unwrap();
+
+    assert_results(
+        engine.parse_results(
+            include_bytes!("fixtures/json_engine/results.json"),
+            &base_url,
+        ),
+        "example",
+        &[
+            (
+                "Rust (programming language)",
+                "https://search.example.com/wiki/Rust",
+                "A general-purpose programming language.",
+            ),
+            (
+                "Rust Programming Language",
+                "https://www.rust-lang.org/",
+                "",
+            ),
+        ],
+    );
+    assert_no_results(engine.parse_results(
+        include_bytes!("fixtures/json_engine/no_results.json"),
+        &base_url,
+    ));
+}
@@ -0,0 +1,229 @@
+//! Integration tests of the declarative json api engines against a local mock http server.
+
+use std::{collections::BTreeMap, net::TcpListener};
+
+use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+use crabbysearch::{
+    config::{Config, DeclarativeEngineConfig, JsonEngineConfig},
+    engines::{json_engine::JsonEngine, EngineRegistry},
+    models::engine_models::{EngineError, SearchEngine},
+    results::browser_profile::random_browser_profile,
+};
+use reqwest::Client;
+use serde_json::json;
+
+/// Responds with two mapped results, one result without a title and one with a script link. The query and the page are
+/// echoed in the title of the first result and the `X-Api-Key` header in the second description.
+async fn search(req: HttpRequest, params: web::Query<BTreeMap<String, String>>) -> HttpResponse {
+    let api_key = req
+        .headers()
+        .get("X-Api-Key")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+
+    HttpResponse::Ok().json(json!({
+        "data": {
+            "items": [
+                {
+                    "title": format!("{} page {}", params["q"], params["page"]),
+                    "links": { "html": "/docs/1" },
+                    "summary": "The first result"
+                },
+                {
+                    "title": "The second result",
+                    "links": { "html": "https://example.com/2" },
+                    "summary": api_key
+                },
+                {
+                    "links": { "html": "https://example.com/untitled" }
+                },
+                {
+                    "title": "A script link",
+                    "links": { "html": "javascript:alert(1)" }
+                }
+            ]
+        }
+    }))
+}
+
+/// Responds with an empty result array.
+async fn empty() -> HttpResponse {
+    HttpResponse::Ok().json(json!({ "data": { "items": [] } }))
+}
+
+/// Starts the mock server on a random local port and returns its base url.
+fn mock_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+    let server = HttpServer::new(|| {
+        App::new()
+            .route("/api/search", web::get().to(search))
+            .route("/api/empty", web::get().to(empty))
+    })
+    .workers(1)
+    .listen(listener)
+    .unwrap()
+    .run();
+    actix_web::rt::spawn(server);
+
+    base_url
+}
+
+/// Builds the config of a json engine requesting the given url template.
+fn engine_config(url: String, results: &str) -> JsonEngineConfig {
+    JsonEngineConfig {
+        engine: DeclarativeEngineConfig {
+            display_name: Some("Mock API".to_owned()),
+            categories: vec!["it".to_owned()],
+            url,
+            results_per_page: 10,
+            headers: BTreeMap::from([("X-Api-Key".to_owned(), "secret".to_owned())]),
+            cookies: BTreeMap::new(),
+        },
+        results: results.to_owned(),
+        result_title: "/title".to_owned(),
+        result_url: "/links/html".to_owned(),
+        result_description: Some("/summary".to_owned()),
+    }
+}
+
+#[actix_web::test]
+async fn maps_the_configured_fields() {
+    let base_url = mock_server();
+    let config = engine_config(
+        format!("{base_url}/api/search?q={{query}}&page={{page}}"),
+        "/data/items",
+    );
+    let engine = JsonEngine::new("mock", &config).unwrap();
+
+    let results = engine
+        .results(
+            "rust & serde",
+            1,
+            random_browser_profile(),
+            &Client::new(),
+            0,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(results.len(), 2);
+
+    let (url, first) = &results[0];
+    assert_eq!(url, &format!("{base_url}/docs/1"));
+    assert_eq!(first.url, format!("{base_url}/docs/1"));
+    // the text is escaped as it is rendered as html.
+    assert_eq!(first.title, "rust &amp; serde page 2");
+    assert_eq!(first.description, "The first result");
+    assert_eq!(first.engine, vec!["mock".to_owned()]);
+
+    let (url, second) = &results[1];
+    assert_eq!(url, "https://example.com/2");
+    assert_eq!(second.description, "secret");
+}
+
+#[actix_web::test]
+async fn escapes_the_markup_of_the_fields() {
+    let base_url = mock_server();
+    let config = engine_config(
+        format!("{base_url}/api/search?q={{query}}&page={{page}}"),
+        "/data/items",
+    );
+    let engine = JsonEngine::new("mock", &config).unwrap();
+
+    let results = engine
+        .results(
+            "<script>alert(1)</script>",
+            0,
+            random_browser_profile(),
+            &Client::new(),
+            0,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        results[0].1.title,
+        "&lt;script&gt;alert(1)&lt;/script&gt; page 1"
+    );
+    // the result with the script link is dropped.
+    assert_eq!(results.len(), 2);
+}
+
+#[actix_web::test]
+async fn reports_an_empty_result_array() {
+    let base_url = mock_server();
+    let config = engine_config(format!("{base_url}/api/empty?q={{query}}"), "/data/items");
+    let engine = JsonEngine::new("mock", &config).unwrap();
+
+    let error = engine
+        .results("rust", 0, random_browser_profile(), &Client::new(), 0)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(
+        error.current_context(),
+        EngineError::EmptyResultSet
+    ));
+}
+
+#[actix_web::test]
+async fn reports_a_missing_result_array() {
+    let base_url = mock_server();
+    let config = engine_config(
+        format!("{base_url}/api/search?q={{query}}&page={{page}}"),
+        "/data/missing",
+    );
+    let engine = JsonEngine::new("mock", &config).unwrap();
+
+    let error = engine
+        .results("rust", 0, random_browser_profile(), &Client::new(), 0)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error.current_context(), EngineError::ParseFailure));
+}
+
+#[test]
+fn rejects_invalid_json_pointers() {
+    let config = engine_config("https://example.com/?q={query}".to_owned(), "data.items");
+
+    assert!(JsonEngine::new("mock", &config).is_err());
+}
+
+#[test]
+fn registers_the_engines_of_the_config() {
+    let mut config = Config::default();
+    config.json_engines.insert(
+        "mock".to_owned(),
+        engine_config(
+            "https://example.com/?q={query}&page={page}".to_owned(),
+            "/data",
+        ),
+    );
+    config.json_engines.insert(
+        "invalid".to_owned(),
+        engine_config("https://example.com/?q={query}".to_owned(), "data"),
+    );
+
+    let registry = EngineRegistry::new(&config);
+
+    let info = registry.get("mock").unwrap().info();
+    assert_eq!(info.display_name, "Mock API");
+    assert_eq!(info.categories, vec!["it".to_owned()]);
+    assert!(info.supports_paging);
+    assert!(!info.supports_safe_search);
+
+    assert!(matches!(
+        registry.get("invalid"),
+        Err(EngineError::NoSuchEngineFound(_))
+    ));
+}
+
+#[test]
+fn rejects_an_invalid_url_template() {
+    let config = engine_config("https://exa mple.com/?q={query}".to_owned(), "/data/items");
+
+    assert!(JsonEngine::new("mock", &config).is_err());
+}
@@ -0,0 +1,1535 @@
+//! End-to-end tests of the search route and the other routes of the server against a local mock
+//! server which impersonates the upstream engines. The mock engines serve the saved fixtures of
+//! `tests/fixtures`, with injected latency, errors, rate limits, empty result sets, captcha pages
+//! and changed markup.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    io::{Read, Write},
+    net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use actix_web::{
+    body::MessageBody,
+    cookie::Cookie,
+    dev::ServiceResponse,
+    http::header::{self, HeaderMap},
+    middleware::from_fn,
+    test, web, App, HttpRequest, HttpResponse, HttpServer,
+};
+use crabbysearch::{
+    cache::Cache,
+    config::{
+        CircuitBreakerConfig, Config, DeclarativeEngineConfig, EnginePolicy, EnginePolicyOverride,
+        HtmlEngineConfig,
+    },
+    engines::{searx::Searx, EngineRegistry},
+    health::EngineHealth,
+    metrics::{track_requests, Metrics},
+    models::{
+        aggregation_models::{SearchResult, SearchResults},
+        engine_models::{EngineError, EngineHandler, SearchEngine},
+        health_models::{EngineState, HealthReport},
+    },
+    results::{
+        aggregator::aggregate,
+        browser_profile::{random_browser_profile, BrowserProfile},
+        filter::load_filter_lists,
+    },
+    server::{
+        router::{health_check, opensearch, prometheus_metrics, settings, status},
+        routes::{search::search, suggest::suggest},
+    },
+};
+use error_stack::Result;
+use reqwest::Client;
+
+/// The page which the mock librex instance and the first searx instance answer with instead of a
+/// result page.
+const CAPTCHA_PAGE: &str = r#"<!DOCTYPE html>
+<html><body><form id="captcha-form"><div class="g-recaptcha" data-sitekey="key"></div></form></body></html>"#;
+
+/// The bing result page which the mock escaping engine answers with, whose result holds markup
+/// and characters which must be escaped in the xml documents.
+const ESCAPING_PAGE: &str = r#"<!DOCTYPE html>
+<html><body><ol id="b_results"><li class="b_algo">
+<div class="tpcn"><a class="tilk" href="https://example.com/search?a=1&amp;b=2"></a></div>
+<h2><a href="https://example.com/search?a=1&amp;b=2">Rust &amp; &lt;Cargo&gt; <em>tips</em></a></h2>
+<div class="b_caption"><p>1 &lt; 2 &amp;&amp; <b>bold</b> "quoted"</p></div>
+</li></ol></body></html>"#;
+
+/// Counts the requests received by the mock upstream engines.
+#[derive(Default)]
+struct Requests(AtomicUsize);
+
+/// Stores the query strings received by the flaky mock engine, which fails the first request of
+/// every query string.
+#[derive(Default)]
+struct Flaky(Mutex<HashSet<String>>);
+
+/// Responds with the given html page after counting the request.
+fn page(requests: &Requests, html: &'static str) -> HttpResponse {
+    requests.0.fetch_add(1, Ordering::Relaxed);
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
+
+/// Responds with the suggestions in the opensearch suggestions format, echoing the query which is
+/// read from the given search url parameter.
+fn suggestions(
+    params: &BTreeMap<String, String>,
+    parameter: &str,
+    suggestions: &[&str],
+) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("application/x-suggestions+json")
+        .body(serde_json::to_string(&(&params[parameter], suggestions)).unwrap())
+}
+
+/// Starts the mock upstream engines on a random local port and returns their base url with the
+/// counter of the received requests.
+fn mock_upstream() -> (String, web::Data<Requests>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = web::Data::new(Requests::default());
+
+    let app_requests = requests.clone();
+    let flaky = web::Data::new(Flaky::default());
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(app_requests.clone())
+            .app_data(flaky.clone())
+            .route(
+                "/bing/search",
+                web::get().to(|requests: web::Data<Requests>| async move {
+                    page(&requests, include_str!("fixtures/bing/results.html"))
+                }),
+            )
+            .route(
+                "/startpage/do/dsearch",
+                web::get().to(|requests: web::Data<Requests>| async move {
+                    page(&requests, include_str!("fixtures/startpage/results.html"))
+                }),
+            )
+            .route(
+                "/brave/search",
+                web::get().to(|requests: web::Data<Requests>| async move {
+                    page(&requests, include_str!("fixtures/brave/no_results.html"))
+                }),
+            )
+            .route(
+                // slower than the request timeout of the client.
+                "/duckduckgo/html/",
+                web::get().to(|requests: web::Data<Requests>| async move {
+                    actix_web::rt::time::sleep(Duration::from_millis(1500)).await;
+                    page(&requests, include_str!("fixtures/duckduckgo/results.html"))
+                }),
+            )
+            .route(
+                // slow, but faster than the request timeout of the client.
+                "/searx/search",
+                web::get().to(|requests: web::Data<Requests>| async move {
+                    actix_web::rt::time::sleep(Duration::from_millis(300)).await;
+                    page(&requests, include_str!("fixtures/searx/results.html"))
+                }),
+            )
+            .route(
+                // a bot challenge with an error status, the engine fails over to the next instance.
+                "/searx-challenge/search",
+                web::get().to(|requests: web::Data<Requests>| async move {
+                    requests.0.fetch_add(1, Ordering::Relaxed);
+                    HttpResponse::ServiceUnavailable()
+                        .content_type("text/html")
+                        .body(CAPTCHA_PAGE)
+                }),
+            )
+            .route(
+                "/librex/search.php",
+                web::get().to(|requests: web::Data<Requests>| async move {
+                    page(&requests, CAPTCHA_PAGE)
+                }),
+            )
+            .route(
+                "/mojeek/search",
+                web::get().to(|requests: web::Data<Requests>| async move {
+                    requests.0.fetch_add(1, Ordering::Relaxed);
+                    HttpResponse::TooManyRequests()
+                        .insert_header(("Retry-After", "60"))
+                        .finish()
+                }),
+            )
+            .route(
+                // a transient failure which succeeds when retried.
+                "/flaky/search",
+                web::get().to(
+                    |request: HttpRequest,
+                     requests: web::Data<Requests>,
+                     flaky: web::Data<Flaky>| async move {
+                        let query = request.query_string().to_owned();
+                        if flaky.0.lock().unwrap().insert(query) {
+                            requests.0.fetch_add(1, Ordering::Relaxed);
+                            return HttpResponse::BadGateway().finish();
+                        }
+                        page(&requests, include_str!("fixtures/bing/results.html"))
+                    },
+                ),
+            )
+            .route(
+                "/bing/osjson.aspx",
+                web::get().to(|params: web::Query<BTreeMap<String, String>>| async move {
+                    suggestions(&params, "query", &["rust lang", "Rust Book", "rustup"])
+                }),
+            )
+            .route(
+                "/brave/api/suggest",
+                web::get().to(|params: web::Query<BTreeMap<String, String>>| async move {
+                    suggestions(&params, "q", &["Rust Lang", "rust analyzer"])
+                }),
+            )
+            .route(
+                "/duckduckgo/ac/",
+                web::get().to(|params: web::Query<BTreeMap<String, String>>| async move {
+                    suggestions(&params, "q", &["RUSTUP", "rust cargo"])
+                }),
+            )
+            .route(
+                "/escaping/search",
+                web::get().to(|requests: web::Data<Requests>| async move {
+                    page(&requests, ESCAPING_PAGE)
+                }),
+            )
+            .route(
+                // a page whose markup does not match the selectors of the engine.
+                "/example/search",
+                web::get().to(|requests: web::Data<Requests>| async move {
+                    page(&requests, include_str!("fixtures/bing/results.html"))
+                }),
+            )
+    })
+    .workers(2)
+    .listen(listener)
+    .unwrap()
+    .run();
+    actix_web::rt::spawn(server);
+
+    (base_url, requests)
+}
+
+/// Returns the base url of a local port on which nothing listens.
+fn closed_port() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    format!("http://{}", listener.local_addr().unwrap())
+}
+
+/// Builds the config querying the mock upstream engines over plain http.
+fn config(base_url: &str) -> Config {
+    let mut config = Config {
+        https_only: false,
+        request_timeout: 1,
+        upstream_search_engines: [
+            "bing",
+            "startpage",
+            "brave",
+            "duckduckgo",
+            "searx",
+            "librex",
+            "mojeek",
+            "example",
+        ]
+        .map(String::from)
+        .to_vec(),
+        engine_base_urls: BTreeMap::from([
+            ("bing".to_owned(), format!("{base_url}/bing")),
+            ("startpage".to_owned(), closed_port()),
+            ("brave".to_owned(), format!("{base_url}/brave")),
+            ("duckduckgo".to_owned(), format!("{base_url}/duckduckgo")),
+            ("mojeek".to_owned(), format!("{base_url}/mojeek")),
+        ]),
+        html_engines: BTreeMap::from([(
+            "example".to_owned(),
+            HtmlEngineConfig {
+                engine: DeclarativeEngineConfig {
+                    display_name: None,
+                    categories: vec!["general".to_owned()],
+                    url: format!("{base_url}/example/search?q={{query}}"),
+                    results_per_page: 10,
+                    headers: BTreeMap::new(),
+                    cookies: BTreeMap::new(),
+                },
+                no_results: ".notice".to_owned(),
+                no_results_marker: None,
+                results: "#results>.result".to_owned(),
+                result_title: "a.title".to_owned(),
+                result_url: "a.title".to_owned(),
+                result_description: "p.snippet".to_owned(),
+            },
+        )]),
+        ..Config::default()
+    };
+    config.searx.pool.instances = vec![
+        format!("{base_url}/searx-challenge"),
+        format!("{base_url}/searx"),
+    ];
+    config.librex.instances = vec![format!("{base_url}/librex")];
+    config
+}
+
+/// Builds the config querying bing, which answers right away, and searx, which answers after 300
+/// ms.
+fn fast_and_slow_config(base_url: &str) -> Config {
+    let mut config = config(base_url);
+    config.upstream_search_engines = ["bing", "searx"].map(String::from).to_vec();
+    config
+}
+
+/// Maps the urls of the results to the sorted names of the engines which provided them.
+fn result_engines(results: &SearchResults) -> BTreeMap<&str, Vec<&str>> {
+    results
+        .results
+        .iter()
+        .map(|result| {
+            let mut engines: Vec<&str> = result.engine.iter().map(String::as_str).collect();
+            engines.sort();
+            (result.url.as_str(), engines)
+        })
+        .collect()
+}
+
+/// The state of the server which is shared by the apps built in a test, so that it outlives a
+/// single request.
+struct State {
+    /// The cache of the search results.
+    cache: Cache,
+    /// The health state of the upstream engines.
+    health: EngineHealth,
+    /// The metrics of the server.
+    metrics: Metrics,
+}
+
+impl State {
+    /// Builds the state of a freshly started server.
+    fn new(config: &Config) -> Self {
+        Self {
+            cache: Cache::build(config),
+            health: EngineHealth::build(config),
+            metrics: Metrics::new(),
+        }
+    }
+}
+
+/// Sends the request to an app built from the config and the shared state, with the routes and
+/// the middleware of the server, and returns the response.
+async fn call(
+    config: &Config,
+    state: &State,
+    request: test::TestRequest,
+) -> ServiceResponse<impl MessageBody> {
+    let app = test::init_service(
+        App::new()
+            .wrap(from_fn(track_requests))
+            .app_data(web::Data::new(config.clone()))
+            .app_data(web::Data::new(state.cache.clone()))
+            .app_data(web::Data::new(state.health.clone()))
+            .app_data(web::Data::new(state.metrics.clone()))
+            .app_data(web::Data::new(EngineRegistry::new(config)))
+            .service(search)
+            .service(suggest)
+            .service(opensearch)
+            .service(settings)
+            .service(health_check)
+            .service(status)
+            .service(prometheus_metrics),
+    )
+    .await;
+
+    test::call_service(&app, request.to_request()).await
+}
+
+/// Requests the uri from an app built from the config and the shared state and returns the body
+/// of the response.
+async fn get(config: &Config, state: &State, uri: &str) -> web::Bytes {
+    test::read_body(call(config, state, test::TestRequest::get().uri(uri)).await).await
+}
+
+/// Searches for the query through the search route.
+async fn search_json(config: &Config, state: &State, query: &str) -> SearchResults {
+    let body = get(config, state, &format!("/search?q={query}&format=json")).await;
+    serde_json::from_slice(&body).unwrap()
+}
+
+/// Searches for the query through the search route on the given safe search level.
+async fn safe_search_json(
+    config: &Config,
+    state: &State,
+    query: &str,
+    safe_search_level: u8,
+) -> SearchResults {
+    let uri = format!("/search?q={query}&safesearch={safe_search_level}&format=json");
+    serde_json::from_slice(&get(config, state, &uri).await).unwrap()
+}
+
+/// Returns the content type of the response.
+fn content_type(response: &ServiceResponse<impl MessageBody>) -> &str {
+    response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .unwrap()
+        .to_str()
+        .unwrap()
+}
+
+/// Requests the uri and returns the body of the response as text.
+async fn get_text(config: &Config, state: &State, uri: &str) -> String {
+    String::from_utf8(get(config, state, uri).await.to_vec()).unwrap()
+}
+
+#[actix_web::test]
+async fn aggregates_the_results_and_the_engine_errors() {
+    let (base_url, _) = mock_upstream();
+    let config = config(&base_url);
+    let state = State::new(&config);
+
+    let results = search_json(&config, &state, "rust%20programming").await;
+
+    // the duplicate results of the engines are merged.
+    assert_eq!(
+        result_engines(&results),
+        BTreeMap::from([
+            ("https://doc.rust-lang.org/book/", vec!["bing", "searx"]),
+            ("https://www.rust-lang.org/", vec!["bing", "searx"]),
+        ])
+    );
+    assert_eq!(results.results.len(), 2);
+
+    let errors: BTreeMap<&str, &str> = results
+        .engine_errors_info
+        .iter()
+        .map(|info| (info.engine.as_str(), info.error.as_str()))
+        .collect();
+    assert_eq!(
+        errors,
+        BTreeMap::from([
+            ("brave", "EmptyResultSet"),
+            ("duckduckgo", "Timeout"),
+            ("example", "ParseFailure"),
+            ("librex", "Captcha"),
+            ("mojeek", "RateLimited"),
+            ("startpage", "RequestError"),
+        ])
+    );
+}
+
+#[actix_web::test]
+async fn caches_the_results() {
+    let (base_url, requests) = mock_upstream();
+    let config = config(&base_url);
+    let state = State::new(&config);
+
+    let first = search_json(&config, &state, "rust").await;
+    let upstream_requests = requests.0.load(Ordering::Relaxed);
+    assert!(upstream_requests > 0);
+
+    // the current page is served from the cache without querying the upstream engines again.
+    let cached = search_json(&config, &state, "rust").await;
+    assert_eq!(requests.0.load(Ordering::Relaxed), upstream_requests);
+    assert_eq!(cached.results.len(), first.results.len());
+    assert_eq!(
+        cached.engine_errors_info.len(),
+        first.engine_errors_info.len()
+    );
+
+    // another query is not served from the cache.
+    search_json(&config, &state, "serde").await;
+    assert!(requests.0.load(Ordering::Relaxed) > upstream_requests);
+}
+
+#[actix_web::test]
+async fn applies_the_engine_policies() {
+    let (base_url, _) = mock_upstream();
+    let mut config = fast_and_slow_config(&base_url);
+    config
+        .engine_base_urls
+        .insert("bing".to_owned(), format!("{base_url}/flaky"));
+    config.engine_policy = EnginePolicy {
+        timeout: 1000,
+        retries: 1,
+        backoff: 50,
+    };
+    // the searx instance answers after 300 ms.
+    config.engine_policies.insert(
+        "searx".to_owned(),
+        EnginePolicyOverride {
+            timeout: Some(100),
+            retries: Some(0),
+            backoff: None,
+        },
+    );
+    let state = State::new(&config);
+
+    let results = search_json(&config, &state, "rust").await;
+
+    // the first request to bing failed and was retried.
+    assert_eq!(results.results.len(), 2);
+    assert!(results
+        .results
+        .iter()
+        .all(|result| result.engine == vec!["bing".to_owned()]));
+
+    let errors: Vec<(&str, &str)> = results
+        .engine_errors_info
+        .iter()
+        .map(|info| (info.engine.as_str(), info.error.as_str()))
+        .collect();
+    assert_eq!(errors, vec![("searx", "Timeout")]);
+
+    let times = &results.engine_response_times;
+    assert_eq!(times.keys().collect::<Vec<_>>(), ["bing", "searx"]);
+    assert!(times["bing"] >= 50, "{times:?}");
+    assert!(times["searx"] < 300, "{times:?}");
+}
+
+#[actix_web::test]
+async fn returns_the_partial_results_at_the_deadline() {
+    let (base_url, _) = mock_upstream();
+    let config = Config {
+        aggregation_deadline: Some(150),
+        cache_late_results: true,
+        ..fast_and_slow_config(&base_url)
+    };
+    let state = State::new(&config);
+
+    let partial = search_json(&config, &state, "rust").await;
+
+    assert_eq!(
+        result_engines(&partial),
+        BTreeMap::from([
+            ("https://doc.rust-lang.org/book/", vec!["bing"]),
+            ("https://www.rust-lang.org/", vec!["bing"]),
+        ])
+    );
+    let errors: Vec<(&str, &str)> = partial
+        .engine_errors_info
+        .iter()
+        .map(|info| (info.engine.as_str(), info.error.as_str()))
+        .collect();
+    assert_eq!(errors, vec![("searx", "Timeout")]);
+
+    // the late results of searx are merged into the cached results.
+    actix_web::rt::time::sleep(Duration::from_millis(600)).await;
+    let complete = search_json(&config, &state, "rust").await;
+
+    assert_eq!(
+        result_engines(&complete),
+        BTreeMap::from([
+            ("https://doc.rust-lang.org/book/", vec!["bing", "searx"]),
+            ("https://www.rust-lang.org/", vec!["bing", "searx"]),
+        ])
+    );
+    assert!(complete.engine_errors_info.is_empty());
+    assert!(complete.engine_response_times["searx"] >= 300);
+
+    // the late engine is recorded with its real outcome, not as timed out.
+    let report = state.health.report(["searx"].into_iter());
+    let searx = &report.engines["searx"];
+    assert_eq!(searx.state, EngineState::Healthy);
+    assert_eq!(searx.last_error, None);
+    assert_eq!(searx.error_rate, Some(0.0));
+}
+
+#[actix_web::test]
+async fn records_the_aborted_late_engines_as_timed_out() {
+    let (base_url, _) = mock_upstream();
+    let config = Config {
+        aggregation_deadline: Some(150),
+        cache_late_results: false,
+        ..fast_and_slow_config(&base_url)
+    };
+    let state = State::new(&config);
+
+    search_json(&config, &state, "rust").await;
+
+    let report = state.health.report(["searx"].into_iter());
+    let searx = &report.engines["searx"];
+    assert_eq!(searx.last_error.as_deref(), Some("Timeout"));
+    assert_eq!(searx.error_rate, Some(1.0));
+}
+
+#[actix_web::test]
+async fn returns_the_results_once_the_quorum_answered() {
+    let (base_url, _) = mock_upstream();
+    let config = Config {
+        aggregation_quorum: Some(1),
+        cache_late_results: false,
+        ..fast_and_slow_config(&base_url)
+    };
+    let state = State::new(&config);
+
+    let partial = search_json(&config, &state, "rust").await;
+
+    assert_eq!(partial.results.len(), 2);
+    let errors: Vec<(&str, &str)> = partial
+        .engine_errors_info
+        .iter()
+        .map(|info| (info.engine.as_str(), info.error.as_str()))
+        .collect();
+    assert_eq!(errors, vec![("searx", "Timeout")]);
+
+    // the late engine was aborted, the cached results stay partial.
+    actix_web::rt::time::sleep(Duration::from_millis(600)).await;
+    let cached = search_json(&config, &state, "rust").await;
+
+    assert_eq!(cached.engine_errors_info.len(), 1);
+    assert!(!cached.engine_response_times.contains_key("searx"));
+}
+
+/// Requests the results of the searx instances of the config, starting with the first instance.
+async fn searx_results(config: &Config) -> Result<Vec<(String, SearchResult)>, EngineError> {
+    Searx::new(&config.searx)
+        .results("rust", 0, random_browser_profile(), &Client::new(), 0)
+        .await
+}
+
+#[actix_web::test]
+async fn fails_over_to_the_next_instance() {
+    let (base_url, requests) = mock_upstream();
+    let mut config = config(&base_url);
+    // the instances fail with a request error, a captcha and a rate limit.
+    let failing_instances = vec![
+        closed_port(),
+        format!("{base_url}/searx-challenge"),
+        format!("{base_url}/mojeek"),
+    ];
+    // every failing instance is skipped in order until the last instance answers.
+    config.searx.pool.instances = failing_instances.clone();
+    config
+        .searx
+        .pool
+        .instances
+        .push(format!("{base_url}/searx"));
+    let results = searx_results(&config).await.unwrap();
+    let urls: Vec<&str> = results.iter().map(|(url, _)| url.as_str()).collect();
+    assert_eq!(
+        urls,
+        [
+            "https://www.rust-lang.org/",
+            "https://doc.rust-lang.org/book/"
+        ]
+    );
+    assert_eq!(requests.0.load(Ordering::Relaxed), 3);
+
+    // the error of the last instance is returned once all the instances failed.
+    config.searx.pool.instances = failing_instances;
+    let error = searx_results(&config).await.unwrap_err();
+    assert_eq!(error.current_context().kind(), "RateLimited");
+    assert_eq!(requests.0.load(Ordering::Relaxed), 5);
+}
+
+#[actix_web::test]
+async fn skips_the_failing_engines_for_a_cool_down_period() {
+    let (base_url, requests) = mock_upstream();
+    let config = Config {
+        upstream_search_engines: vec!["mojeek".to_owned()],
+        circuit_breaker: CircuitBreakerConfig {
+            failure_threshold: 2,
+            cool_down: 1,
+        },
+        ..config(&base_url)
+    };
+    let state = State::new(&config);
+    let errors = |results: &SearchResults| -> Vec<String> {
+        results
+            .engine_errors_info
+            .iter()
+            .map(|info| format!("{}: {}", info.engine, info.error))
+            .collect()
+    };
+
+    // the current and the next page are requested, but only the failure of the requested page
+    // counts towards the failure threshold.
+    let failed = search_json(&config, &state, "rust").await;
+    assert_eq!(errors(&failed), ["mojeek: RateLimited"]);
+    assert_eq!(requests.0.load(Ordering::Relaxed), 2);
+    assert!(!get_text(&config, &state, "/settings")
+        .await
+        .contains("temporarily disabled"));
+
+    // the second failing search reaches the failure threshold.
+    let failed = search_json(&config, &state, "serde").await;
+    assert_eq!(errors(&failed), ["mojeek: RateLimited"]);
+    assert_eq!(requests.0.load(Ordering::Relaxed), 4);
+    assert!(get_text(&config, &state, "/settings")
+        .await
+        .contains("temporarily disabled"));
+
+    let skipped = search_json(&config, &state, "actix").await;
+    assert_eq!(errors(&skipped), ["mojeek: TemporarilyDisabled"]);
+    assert_eq!(requests.0.load(Ordering::Relaxed), 4);
+
+    // after the cool-down period a single request probes the engine, which still fails, the
+    // prefetched next page does not probe it.
+    actix_web::rt::time::sleep(Duration::from_millis(1100)).await;
+    assert!(!get_text(&config, &state, "/settings")
+        .await
+        .contains("temporarily disabled"));
+    let probed = search_json(&config, &state, "tokio").await;
+    assert_eq!(errors(&probed), ["mojeek: RateLimited"]);
+    assert_eq!(requests.0.load(Ordering::Relaxed), 5);
+    assert!(get_text(&config, &state, "/settings")
+        .await
+        .contains("temporarily disabled"));
+}
+
+#[actix_web::test]
+async fn reports_the_engine_health() {
+    let (base_url, _) = mock_upstream();
+    let config = Config {
+        upstream_search_engines: ["bing", "brave", "mojeek"].map(String::from).to_vec(),
+        ..config(&base_url)
+    };
+    let state = State::new(&config);
+
+    // the current and the next page are requested from every engine, but only the requested
+    // page is recorded in the engine health.
+    search_json(&config, &state, "rust").await;
+
+    let report: HealthReport =
+        serde_json::from_slice(&get(&config, &state, "/health").await).unwrap();
+
+    assert_eq!(report.status, "ok");
+    let bing = &report.engines["bing"];
+    assert_eq!(bing.state, EngineState::Healthy);
+    assert!(bing.last_success.is_some());
+    assert_eq!(bing.last_error, None);
+    assert_eq!((bing.requests, bing.error_rate), (1, Some(0.0)));
+    assert!(bing.p50_latency.is_some() && bing.p95_latency >= bing.p50_latency);
+
+    // an empty result set is not an error of the engine.
+    assert_eq!(report.engines["brave"].state, EngineState::Healthy);
+
+    let mojeek = &report.engines["mojeek"];
+    assert_eq!(mojeek.state, EngineState::Failing);
+    assert_eq!(mojeek.last_success, None);
+    assert_eq!(mojeek.last_error.as_deref(), Some("RateLimited"));
+    assert!(mojeek.last_error_at.is_some());
+    assert_eq!((mojeek.requests, mojeek.error_rate), (1, Some(1.0)));
+
+    let duckduckgo = &report.engines["duckduckgo"];
+    assert_eq!(duckduckgo.state, EngineState::Unknown);
+    assert_eq!((duckduckgo.requests, duckduckgo.error_rate), (0, None));
+
+    let page = get_text(&config, &state, "/status").await;
+    assert!(page.contains("href=\"status\""));
+    assert!(page.contains("RateLimited"));
+    assert!(page.contains("100 %"));
+}
+
+/// An engine whose task panics, standing in for a bug in the code of an engine.
+struct PanickingEngine;
+
+#[async_trait::async_trait]
+impl SearchEngine for PanickingEngine {
+    async fn results(
+        &self,
+        _query: &str,
+        _page: u32,
+        _profile: &BrowserProfile,
+        _client: &Client,
+        _safe_search: u8,
+    ) -> Result<Vec<(String, SearchResult)>, EngineError> {
+        panic!("the engine crashed")
+    }
+}
+
+#[actix_web::test]
+async fn reports_a_panicking_engine_as_an_unexpected_error() {
+    let (base_url, _) = mock_upstream();
+    let config = config(&base_url);
+    let state = State::new(&config);
+    let registry = EngineRegistry::new(&config);
+    let info = registry.get("bing").unwrap().info().clone();
+    let engines = [
+        EngineHandler::new("panicking", info, Arc::new(PanickingEngine)),
+        registry.get("bing").unwrap().clone(),
+    ];
+
+    let aggregation = aggregate(
+        "rust",
+        0,
+        0,
+        web::Data::new(config),
+        &state.health,
+        true,
+        &state.metrics,
+        &engines,
+    )
+    .await
+    .unwrap();
+
+    // the results of the other engines are still aggregated.
+    let results = aggregation.results;
+    assert_eq!(results.results.len(), 2);
+    let errors: Vec<(&str, &str)> = results
+        .engine_errors_info
+        .iter()
+        .map(|info| (info.engine.as_str(), info.error.as_str()))
+        .collect();
+    assert_eq!(errors, [("panicking", "UnexpectedError")]);
+
+    // the failure is recorded in the health of the engine.
+    let report = state.health.report(["panicking"].into_iter());
+    let panicking = &report.engines["panicking"];
+    assert_eq!(panicking.last_error.as_deref(), Some("UnexpectedError"));
+    assert_eq!((panicking.requests, panicking.error_rate), (1, Some(1.0)));
+}
+
+#[actix_web::test]
+async fn exports_the_metrics() {
+    let (base_url, _) = mock_upstream();
+    let config = Config {
+        upstream_search_engines: ["bing", "mojeek", "searx"].map(String::from).to_vec(),
+        ..config(&base_url)
+    };
+    let state = State::new(&config);
+
+    // the current and the next page are aggregated, then served from the cache.
+    search_json(&config, &state, "rust").await;
+    search_json(&config, &state, "rust").await;
+    get(&config, &state, "/unknown").await;
+
+    let metrics = get_text(&config, &state, "/metrics").await;
+    let value = |series: &str| -> f64 {
+        metrics
+            .lines()
+            .find_map(|line| line.strip_prefix(series)?.strip_prefix(' '))
+            .unwrap_or_else(|| panic!("{series} is missing in\n{metrics}"))
+            .parse()
+            .unwrap()
+    };
+
+    assert_eq!(
+        value(r#"crabbysearch_http_requests_total{method="GET",route="/search",status="200"}"#),
+        2.0
+    );
+    assert_eq!(
+        value(r#"crabbysearch_http_request_duration_seconds_count{route="/search"}"#),
+        2.0
+    );
+    assert_eq!(
+        value(r#"crabbysearch_http_requests_total{method="GET",route="unmatched",status="404"}"#),
+        1.0
+    );
+    assert_eq!(
+        value(r#"crabbysearch_engine_requests_total{engine="bing"}"#),
+        2.0
+    );
+    assert_eq!(
+        value(r#"crabbysearch_engine_errors_total{engine="mojeek",kind="RateLimited"}"#),
+        2.0
+    );
+    assert_eq!(
+        value(r#"crabbysearch_engine_request_duration_seconds_count{engine="searx",outcome="ok"}"#),
+        2.0
+    );
+    assert_eq!(
+        value(
+            r#"crabbysearch_engine_request_duration_seconds_count{engine="mojeek",outcome="RateLimited"}"#
+        ),
+        2.0
+    );
+    assert_eq!(value("crabbysearch_cache_misses_total"), 2.0);
+    assert_eq!(value("crabbysearch_cache_hits_total"), 2.0);
+    assert_eq!(value("crabbysearch_results_per_query_count"), 2.0);
+    assert_eq!(value("crabbysearch_results_per_query_sum"), 4.0);
+    // both results of bing are merged with the results of searx on both pages.
+    assert_eq!(value("crabbysearch_merged_results_total"), 4.0);
+}
+
+/// The urls and the headers of the requests received by the recording upstream.
+type Recorded = web::Data<Mutex<Vec<(String, HeaderMap)>>>;
+
+/// Starts an upstream on a random local port which answers every request with the bing fixture
+/// and records the requests, and returns its url with the recorded requests. It accepts absolute
+/// urls too, so it also serves as a http proxy stand-in.
+fn recording_upstream() -> (String, Recorded) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let recorded: Recorded = web::Data::new(Mutex::new(Vec::new()));
+
+    let app_recorded = recorded.clone();
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(app_recorded.clone())
+            .default_service(web::to(
+                |request: HttpRequest, recorded: Recorded| async move {
+                    recorded
+                        .lock()
+                        .unwrap()
+                        .push((request.uri().to_string(), request.headers().clone()));
+                    HttpResponse::Ok()
+                        .content_type("text/html")
+                        .body(include_str!("fixtures/bing/results.html"))
+                },
+            ))
+    })
+    .workers(1)
+    .listen(listener)
+    .unwrap()
+    .run();
+    actix_web::rt::spawn(server);
+
+    (url, recorded)
+}
+
+/// Starts a socks5 proxy stand-in on a random local port which connects every requested host to
+/// the target address, and returns its url with the hosts requested through it.
+fn socks_proxy(target: SocketAddr) -> (String, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("socks5h://{}", listener.local_addr().unwrap());
+    let requested: Arc<Mutex<Vec<String>>> = Arc::default();
+
+    let connection_requested = requested.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let requested = connection_requested.clone();
+            std::thread::spawn(move || socks_connection(stream, target, &requested));
+        }
+    });
+
+    (url, requested)
+}
+
+/// Handles a connection to the socks5 proxy stand-in: it accepts the connection without
+/// authentication, records the requested host and relays the data to and from the target.
+fn socks_connection(
+    mut client: TcpStream,
+    target: SocketAddr,
+    requested: &Mutex<Vec<String>>,
+) -> std::io::Result<()> {
+    // the greeting lists the authentication methods, of which `no authentication` is chosen.
+    let mut greeting = [0u8; 2];
+    client.read_exact(&mut greeting)?;
+    client.read_exact(&mut vec![0u8; greeting[1] as usize])?;
+    client.write_all(&[5, 0])?;
+
+    // the connect request holds the version, the command, a reserved byte and the address.
+    let mut request = [0u8; 4];
+    client.read_exact(&mut request)?;
+    let host = match request[3] {
+        1 => {
+            let mut ip = [0u8; 4];
+            client.read_exact(&mut ip)?;
+            Ipv4Addr::from(ip).to_string()
+        }
+        3 => {
+            let mut len = [0u8; 1];
+            client.read_exact(&mut len)?;
+            let mut name = vec![0u8; len[0] as usize];
+            client.read_exact(&mut name)?;
+            String::from_utf8_lossy(&name).into_owned()
+        }
+        _ => return Ok(()),
+    };
+    let mut port = [0u8; 2];
+    client.read_exact(&mut port)?;
+    requested
+        .lock()
+        .unwrap()
+        .push(format!("{host}:{}", u16::from_be_bytes(port)));
+
+    let upstream = TcpStream::connect(target)?;
+    client.write_all(&[5, 0, 0, 1, 0, 0, 0, 0, 0, 0])?;
+
+    let (mut client_reader, mut upstream_writer) = (client.try_clone()?, upstream.try_clone()?);
+    std::thread::spawn(move || std::io::copy(&mut client_reader, &mut upstream_writer));
+    std::io::copy(&mut { upstream }, &mut client)?;
+    Ok(())
+}
+
+#[actix_web::test]
+async fn routes_the_engines_through_their_proxies() {
+    let (base_url, _) = mock_upstream();
+    let (http_proxy, http_requested) = recording_upstream();
+    let target: SocketAddr = base_url.trim_start_matches("http://").parse().unwrap();
+    let (socks_proxy, socks_requested) = socks_proxy(target);
+
+    let mut config = Config {
+        upstream_search_engines: ["bing", "brave", "mojeek", "searx"]
+            .map(String::from)
+            .to_vec(),
+        proxy: Some(socks_proxy),
+        engine_proxies: BTreeMap::from([
+            ("bing".to_owned(), http_proxy),
+            ("brave".to_owned(), "direct".to_owned()),
+            ("mojeek".to_owned(), "not a proxy url".to_owned()),
+        ]),
+        ..config(&base_url)
+    };
+    // the hosts can only be reached through the proxies.
+    config
+        .engine_base_urls
+        .insert("bing".to_owned(), "http://bing.invalid/bing".to_owned());
+    config.searx.pool.instances = vec!["http://searx.invalid/searx".to_owned()];
+    let state = State::new(&config);
+
+    let results = search_json(&config, &state, "rust").await;
+
+    assert_eq!(
+        result_engines(&results),
+        BTreeMap::from([
+            ("https://doc.rust-lang.org/book/", vec!["bing", "searx"]),
+            ("https://www.rust-lang.org/", vec!["bing", "searx"]),
+        ])
+    );
+    let errors: BTreeMap<&str, &str> = results
+        .engine_errors_info
+        .iter()
+        .map(|info| (info.engine.as_str(), info.error.as_str()))
+        .collect();
+    assert_eq!(
+        errors,
+        BTreeMap::from([("brave", "EmptyResultSet"), ("mojeek", "UnexpectedError")])
+    );
+
+    let http_requested = http_requested.lock().unwrap();
+    assert!(!http_requested.is_empty());
+    assert!(
+        http_requested
+            .iter()
+            .all(|(url, _)| url.starts_with("http://bing.invalid/bing/search?q=rust")),
+        "{http_requested:?}"
+    );
+    assert!(socks_requested
+        .lock()
+        .unwrap()
+        .contains(&"searx.invalid:80".to_owned()));
+}
+
+#[actix_web::test]
+async fn sends_a_coherent_browser_profile_per_engine_request() {
+    let (base_url, recorded) = recording_upstream();
+    let mut config = Config {
+        upstream_search_engines: [
+            "bing",
+            "brave",
+            "duckduckgo",
+            "mojeek",
+            "startpage",
+            "example",
+        ]
+        .map(String::from)
+        .to_vec(),
+        ..config(&base_url)
+    };
+    config
+        .engine_base_urls
+        .insert("startpage".to_owned(), format!("{base_url}/startpage"));
+    config
+        .html_engines
+        .get_mut("example")
+        .unwrap()
+        .engine
+        .headers
+        .insert("Accept-Language".to_owned(), "de-DE".to_owned());
+
+    search_json(&config, &State::new(&config), "rust").await;
+
+    let recorded = recorded.lock().unwrap();
+    let header = |headers: &HeaderMap, name: &str| {
+        headers
+            .get(name)
+            .map(|value| value.to_str().unwrap().to_owned())
+    };
+    for (url, headers) in recorded.iter() {
+        let user_agent = header(headers, "User-Agent").unwrap();
+        assert!(header(headers, "Accept").is_some(), "{url}");
+        assert!(header(headers, "Referer").is_none(), "{url}");
+
+        // only the chromium based browsers send the client hints, with the version of the agent.
+        match user_agent.split_once("Chrome/") {
+            Some((_, version)) => {
+                let brands = header(headers, "Sec-CH-UA").unwrap();
+                let major = version.split('.').next().unwrap();
+                assert!(brands.contains(&format!("v=\"{major}\"")), "{brands}");
+                assert_eq!(
+                    brands.contains("Microsoft Edge"),
+                    user_agent.contains("Edg/")
+                );
+            }
+            None => assert!(header(headers, "Sec-CH-UA").is_none(), "{user_agent}"),
+        }
+
+        // the engine specific headers are added to the headers of the profile.
+        if url.starts_with("/bing/") {
+            assert!(header(headers, "Cookie").is_some());
+        }
+        if url.starts_with("/example/") {
+            assert_eq!(header(headers, "Accept-Language").unwrap(), "de-DE");
+        } else {
+            assert!(header(headers, "Accept-Language").is_some(), "{url}");
+        }
+    }
+
+    // a profile is chosen per engine request, so the engines do not share a single user agent.
+    let user_agents: HashSet<_> = recorded
+        .iter()
+        .map(|(_, headers)| header(headers, "User-Agent"))
+        .collect();
+    assert!(recorded.len() >= 6, "{recorded:?}");
+    assert!(user_agents.len() > 1, "{user_agents:?}");
+}
+
+#[actix_web::test]
+async fn serves_the_results_as_json() {
+    let (base_url, _) = mock_upstream();
+    let config = Config {
+        upstream_search_engines: vec!["bing".to_owned()],
+        ..config(&base_url)
+    };
+    let state = State::new(&config);
+
+    let response = call(
+        &config,
+        &state,
+        test::TestRequest::get().uri("/search?q=rust&page=2&format=json"),
+    )
+    .await;
+
+    assert_eq!(content_type(&response), "application/json");
+    let body: serde_json::Value = serde_json::from_slice(&test::read_body(response).await).unwrap();
+    assert_eq!(body["query"], "rust");
+    assert_eq!(body["page"], 2);
+    assert_eq!(body["previousPage"], 1);
+    assert_eq!(body["nextPage"], 3);
+    assert_eq!(body["results"].as_array().unwrap().len(), 2);
+}
+
+#[actix_web::test]
+async fn filters_the_results_and_disallows_the_queries_of_the_blocklist() {
+    // the filter lists are only used by this test, the others search without safe search.
+    assert!(load_filter_lists(
+        "tests/fixtures/filter/allowlist.txt",
+        "tests/fixtures/filter/blocklist.txt",
+    ));
+    let (base_url, _) = mock_upstream();
+    let mut config = config(&base_url);
+    config.upstream_search_engines = vec!["bing".to_owned()];
+    let state = State::new(&config);
+
+    let urls = |results: &SearchResults| {
+        let mut urls: Vec<String> = results
+            .results
+            .iter()
+            .map(|result| result.url.clone())
+            .collect();
+        urls.sort();
+        urls
+    };
+
+    // below level 3 the filter lists are not applied.
+    let results = safe_search_json(&config, &state, "rust", 2).await;
+    assert!(!results.filtered && !results.disallowed);
+    assert_eq!(
+        urls(&results),
+        [
+            "https://doc.rust-lang.org/book/",
+            "https://www.rust-lang.org/"
+        ]
+    );
+
+    // on level 3 the results of the blocklist are removed, unless they match the allowlist.
+    let results = safe_search_json(&config, &state, "rust", 3).await;
+    assert!(results.filtered && !results.disallowed);
+    assert_eq!(results.safe_search_level, 3);
+    assert_eq!(urls(&results), ["https://doc.rust-lang.org/book/"]);
+
+    // on level 3 a query of the blocklist is still searched.
+    let results = safe_search_json(&config, &state, "casino", 3).await;
+    assert!(!results.disallowed);
+
+    // on level 4 a query of the blocklist is disallowed without searching.
+    let results = safe_search_json(&config, &state, "online%20Casino", 4).await;
+    assert!(results.disallowed && !results.filtered);
+    assert_eq!(results.safe_search_level, 4);
+    assert!(results.results.is_empty() && results.engine_errors_info.is_empty());
+
+    // on level 4 the other queries are searched and filtered as on level 3.
+    let results = safe_search_json(&config, &state, "rust", 4).await;
+    assert!(results.filtered && !results.disallowed);
+    assert_eq!(urls(&results), ["https://doc.rust-lang.org/book/"]);
+}
+
+#[actix_web::test]
+async fn selects_the_engines_of_the_cookie_case_insensitively() {
+    let (base_url, _) = mock_upstream();
+    let mut config = config(&base_url);
+    let example = config.html_engines.remove("example").unwrap();
+    config.html_engines.insert("MyWiki".to_owned(), example);
+    let state = State::new(&config);
+
+    let cookie = r#"{"BING":true,"MyWiki":true,"Nowhere":true,"brave":false}"#;
+    let response = call(
+        &config,
+        &state,
+        test::TestRequest::get()
+            .uri("/search?q=rust&format=json")
+            .cookie(Cookie::new("appCookie", cookie)),
+    )
+    .await;
+    let results: SearchResults = test::read_body_json(response).await;
+
+    assert_eq!(
+        result_engines(&results),
+        BTreeMap::from([
+            ("https://doc.rust-lang.org/book/", vec!["bing"]),
+            ("https://www.rust-lang.org/", vec!["bing"]),
+        ])
+    );
+    let errors: BTreeMap<&str, &str> = results
+        .engine_errors_info
+        .iter()
+        .map(|info| (info.engine.as_str(), info.error.as_str()))
+        .collect();
+    assert_eq!(
+        errors,
+        BTreeMap::from([("MyWiki", "ParseFailure"), ("Nowhere", "EngineNotFound")])
+    );
+}
+
+#[actix_web::test]
+async fn negotiates_the_output_format_with_the_accept_header() {
+    let (base_url, _) = mock_upstream();
+    let config = Config {
+        upstream_search_engines: vec!["bing".to_owned()],
+        ..config(&base_url)
+    };
+    let state = State::new(&config);
+
+    let cases = [
+        (None, "text/html"),
+        (Some("application/json"), "application/json"),
+        (Some("*/*"), "text/html"),
+        (
+            Some("text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8"),
+            "text/html",
+        ),
+        (Some("text/html, application/json;q=0.1"), "text/html"),
+        (
+            Some("application/json;q=0.9, text/html;q=0.8"),
+            "application/json",
+        ),
+        (Some("application/json;q=0, */*"), "text/html"),
+        // the first listed range wins among ranges of equal rank.
+        (Some("application/json, text/html"), "application/json"),
+        (Some("text/html, application/json"), "text/html"),
+        // the ranges which are neither json nor html are skipped.
+        (
+            Some("application/xml, application/json;q=0.5"),
+            "application/json",
+        ),
+    ];
+    for (accept, expected) in cases {
+        let mut request = test::TestRequest::get().uri("/search?q=rust");
+        if let Some(accept) = accept {
+            request = request.insert_header((header::ACCEPT, accept));
+        }
+        let response = call(&config, &state, request).await;
+        assert!(
+            content_type(&response).starts_with(expected),
+            "{accept:?} was answered with {}",
+            content_type(&response)
+        );
+    }
+
+    // the format search url parameter takes precedence over the accept header.
+    let response = call(
+        &config,
+        &state,
+        test::TestRequest::get()
+            .uri("/search?q=rust&format=html")
+            .insert_header((header::ACCEPT, "application/json")),
+    )
+    .await;
+    assert!(content_type(&response).starts_with("text/html"));
+}
+
+/// Returns the first child element of the xml node with the given name.
+fn child<'a, 'input>(node: roxmltree::Node<'a, 'input>, name: &str) -> roxmltree::Node<'a, 'input> {
+    node.children()
+        .find(|child| child.has_tag_name(name))
+        .unwrap_or_else(|| panic!("the {:?} element has no {name} child", node.tag_name()))
+}
+
+/// Builds the config querying bing, which answers with the escaping page.
+fn escaping_config(base_url: &str) -> Config {
+    let mut config = Config {
+        upstream_search_engines: vec!["bing".to_owned()],
+        ..config(base_url)
+    };
+    config
+        .engine_base_urls
+        .insert("bing".to_owned(), format!("{base_url}/escaping"));
+    config
+}
+
+#[actix_web::test]
+async fn serves_the_results_as_a_rss_feed() {
+    let (base_url, _) = mock_upstream();
+    let config = escaping_config(&base_url);
+    let state = State::new(&config);
+    let results = search_json(&config, &state, "rust%20%26%20%3Ccargo%3E").await;
+    let result = &results.results[0];
+
+    let response = call(
+        &config,
+        &state,
+        test::TestRequest::get().uri("/search?q=rust%20%26%20%3Ccargo%3E&format=rss"),
+    )
+    .await;
+    assert_eq!(
+        content_type(&response),
+        "application/rss+xml; charset=utf-8"
+    );
+    let body = String::from_utf8(test::read_body(response).await.to_vec()).unwrap();
+
+    let document = roxmltree::Document::parse(&body).unwrap();
+    let channel = document
+        .descendants()
+        .find(|node| node.has_tag_name("channel"))
+        .unwrap();
+    assert_eq!(
+        child(channel, "title").text(),
+        Some("crabbysearch: rust & <cargo>")
+    );
+    assert_eq!(
+        child(channel, "link").text(),
+        Some("http://localhost:8080/search?q=rust+%26+%3Ccargo%3E")
+    );
+
+    let items: Vec<_> = document
+        .descendants()
+        .filter(|node| node.has_tag_name("item"))
+        .collect();
+    assert_eq!(items.len(), 1);
+    // the markup of the title is stripped and the one of the description is text of the feed,
+    // not xml.
+    assert!(result.title.contains("<em>tips</em>"), "{}", result.title);
+    assert_eq!(child(items[0], "title").text(), Some("Rust & <Cargo> tips"));
+    assert_eq!(
+        child(items[0], "description").text(),
+        Some(result.description.as_str())
+    );
+    assert_eq!(
+        child(items[0], "link").text(),
+        Some("https://example.com/search?a=1&b=2")
+    );
+    assert!(body.contains("<link>https://example.com/search?a=1&amp;b=2</link>"));
+}
+
+#[actix_web::test]
+async fn serves_the_results_as_an_atom_feed() {
+    let (base_url, _) = mock_upstream();
+    let config = escaping_config(&base_url);
+    let state = State::new(&config);
+    let results = search_json(&config, &state, "rust%20%26%20%3Ccargo%3E").await;
+    let result = &results.results[0];
+
+    let response = call(
+        &config,
+        &state,
+        test::TestRequest::get().uri("/search?q=rust%20%26%20%3Ccargo%3E&format=atom"),
+    )
+    .await;
+    assert_eq!(
+        content_type(&response),
+        "application/atom+xml; charset=utf-8"
+    );
+    let body = String::from_utf8(test::read_body(response).await.to_vec()).unwrap();
+
+    let document = roxmltree::Document::parse(&body).unwrap();
+    let feed = document.root_element();
+    assert!(feed.has_tag_name(("http://www.w3.org/2005/Atom", "feed")));
+    assert_eq!(
+        child(feed, "title").text(),
+        Some("crabbysearch: rust & <cargo>")
+    );
+    assert_eq!(
+        child(feed, "id").text(),
+        Some("http://localhost:8080/search?q=rust%20%26%20%3Ccargo%3E&format=atom")
+    );
+
+    let entries: Vec<_> = feed
+        .children()
+        .filter(|node| node.has_tag_name("entry"))
+        .collect();
+    assert_eq!(entries.len(), 1);
+    // the markup of the title is stripped and the one of the description is text of the feed,
+    // not xml.
+    assert_eq!(
+        child(entries[0], "title").text(),
+        Some("Rust & <Cargo> tips")
+    );
+    assert_eq!(
+        child(entries[0], "summary").text(),
+        Some(result.description.as_str())
+    );
+    assert_eq!(
+        child(entries[0], "link").attribute("href"),
+        Some("https://example.com/search?a=1&b=2")
+    );
+}
+
+#[actix_web::test]
+async fn builds_the_feed_links_from_the_public_url() {
+    let (base_url, _) = mock_upstream();
+    let config = Config {
+        public_url: Some("https://public.example.org/crabbysearch/".to_owned()),
+        ..escaping_config(&base_url)
+    };
+    let state = State::new(&config);
+
+    let body = get_text(&config, &state, "/search?q=rust&format=rss").await;
+    let document = roxmltree::Document::parse(&body).unwrap();
+    let channel = document
+        .descendants()
+        .find(|node| node.has_tag_name("channel"))
+        .unwrap();
+    assert_eq!(
+        child(channel, "link").text(),
+        Some("https://public.example.org/crabbysearch/search?q=rust")
+    );
+
+    let body = get_text(&config, &state, "/search?q=rust&format=atom").await;
+    let document = roxmltree::Document::parse(&body).unwrap();
+    let feed = document.root_element();
+    assert_eq!(
+        child(feed, "id").text(),
+        Some("https://public.example.org/crabbysearch/search?q=rust&format=atom")
+    );
+    let links: Vec<_> = feed
+        .children()
+        .filter(|node| node.has_tag_name("link"))
+        .filter_map(|node| node.attribute("href"))
+        .collect();
+    assert_eq!(
+        links,
+        [
+            "https://public.example.org/crabbysearch/search?q=rust&format=atom",
+            "https://public.example.org/crabbysearch/search?q=rust"
+        ]
+    );
+}
+
+#[actix_web::test]
+async fn describes_the_search_and_suggest_templates_for_opensearch() {
+    let config = config(&closed_port());
+    let state = State::new(&config);
+    let templates = |body: &str| -> BTreeMap<String, String> {
+        let document = roxmltree::Document::parse(body).unwrap();
+        document
+            .descendants()
+            .filter(|node| node.has_tag_name("Url"))
+            .map(|node| {
+                (
+                    node.attribute("type").unwrap().to_owned(),
+                    node.attribute("template").unwrap().to_owned(),
+                )
+            })
+            .collect()
+    };
+
+    // the base url is built from the request, as forwarded by a reverse proxy.
+    let response = call(
+        &config,
+        &state,
+        test::TestRequest::get()
+            .uri("/opensearch.xml")
+            .insert_header((header::HOST, "search.example.org"))
+            .insert_header(("X-Forwarded-Proto", "https")),
+    )
+    .await;
+    assert_eq!(
+        content_type(&response),
+        "application/opensearchdescription+xml; charset=utf-8"
+    );
+    let body = String::from_utf8(test::read_body(response).await.to_vec()).unwrap();
+    assert_eq!(
+        templates(&body),
+        BTreeMap::from([
+            (
+                "text/html".to_owned(),
+                "https://search.example.org/search?q={searchTerms}".to_owned()
+            ),
+            (
+                "application/x-suggestions+json".to_owned(),
+                "https://search.example.org/suggest?q={searchTerms}".to_owned()
+            ),
+            (
+                "application/opensearchdescription+xml".to_owned(),
+                "https://search.example.org/opensearch.xml".to_owned()
+            ),
+        ])
+    );
+
+    // the configured public url takes precedence over the request.
+    let config = Config {
+        public_url: Some("https://public.example.org/crabbysearch/".to_owned()),
+        ..config
+    };
+    let body = get_text(&config, &state, "/opensearch.xml").await;
+    assert_eq!(
+        templates(&body)["text/html"],
+        "https://public.example.org/crabbysearch/search?q={searchTerms}"
+    );
+    assert_eq!(
+        templates(&body)["application/x-suggestions+json"],
+        "https://public.example.org/crabbysearch/suggest?q={searchTerms}"
+    );
+}
+
+#[actix_web::test]
+async fn interleaves_the_suggestions_of_the_engines() {
+    let (base_url, _) = mock_upstream();
+    // startpage fails, as nothing listens on its port, and is left out of the suggestions.
+    let config = Config {
+        upstream_search_engines: ["bing", "brave", "duckduckgo", "startpage"]
+            .map(String::from)
+            .to_vec(),
+        ..config(&base_url)
+    };
+    let state = State::new(&config);
+
+    let response = call(
+        &config,
+        &state,
+        test::TestRequest::get().uri("/suggest?q=rust"),
+    )
+    .await;
+    assert_eq!(content_type(&response), "application/x-suggestions+json");
+    let body: (String, Vec<String>) =
+        serde_json::from_slice(&test::read_body(response).await).unwrap();
+
+    // the top suggestions of every engine come first, the duplicates are removed ignoring case.
+    assert_eq!(
+        body,
+        (
+            "rust".to_owned(),
+            [
+                "rust lang",
+                "RUSTUP",
+                "Rust Book",
+                "rust analyzer",
+                "rust cargo"
+            ]
+            .map(String::from)
+            .to_vec()
+        )
+    );
+
+    // an empty query is answered without suggestions.
+    let body = get_text(&config, &state, "/suggest?q=%20").await;
+    assert_eq!(body, r#"["",[]]"#);
+}
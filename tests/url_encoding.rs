@@ -0,0 +1,196 @@
+//! Integration tests of the encoding of the user provided query in the urls of the upstream
+//! engines against a local echo server and a server recording the query strings.
+
+use std::{
+    collections::BTreeMap,
+    net::TcpListener,
+    sync::{Arc, Mutex},
+};
+
+use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+use crabbysearch::{
+    config::{Config, DeclarativeEngineConfig, JsonEngineConfig},
+    engines::{
+        json_engine::JsonEngine, search_result_parser::escape_text, url_builder::build_url,
+        EngineRegistry,
+    },
+    models::engine_models::SearchEngine,
+    results::browser_profile::random_browser_profile,
+};
+use reqwest::Client;
+use serde_json::json;
+
+/// Queries containing reserved characters, non ascii characters and a very long query.
+fn queries() -> Vec<String> {
+    vec![
+        "a&b=c#d+e%f?g/h".to_owned(),
+        "100% \"quoted\" ; semi:colon [brackets] {braces} <tag> |pipe| \\back`tick'".to_owned(),
+        "日本語 café 🦀 Ünïcödé".to_owned(),
+        "  leading and trailing spaces  ".to_owned(),
+        "long query & more ".repeat(500),
+    ]
+}
+
+/// Echoes the decoded query parameters.
+async fn echo(params: web::Query<BTreeMap<String, String>>) -> HttpResponse {
+    HttpResponse::Ok().json(params.into_inner())
+}
+
+/// Echoes the decoded query as the title of a single search result.
+async fn search(params: web::Query<BTreeMap<String, String>>) -> HttpResponse {
+    HttpResponse::Ok().json(json!({
+        "results": [{ "title": params["q"], "url": "https://example.com/" }]
+    }))
+}
+
+/// The raw query strings received by the recording server keyed by the request path.
+type Recorded = Arc<Mutex<BTreeMap<String, String>>>;
+
+/// Starts a server on a random local port which records the raw query string of every request
+/// and answers with an empty page, and returns its base url with the recorded query strings.
+fn recording_server() -> (String, Recorded) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let recorded = Recorded::default();
+
+    let app_recorded = web::Data::from(recorded.clone());
+    let server = HttpServer::new(move || {
+        App::new().app_data(app_recorded.clone()).default_service(web::to(
+            |request: HttpRequest, recorded: web::Data<Mutex<BTreeMap<String, String>>>| async move {
+                recorded.lock().unwrap().insert(
+                    request.path().to_owned(),
+                    request.query_string().to_owned(),
+                );
+                HttpResponse::Ok().content_type("text/html").finish()
+            },
+        ))
+    })
+    .workers(1)
+    .listen(listener)
+    .unwrap()
+    .run();
+    actix_web::rt::spawn(server);
+
+    (base_url, recorded)
+}
+
+/// Starts the echo server on a random local port and returns its base url.
+fn echo_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+    let server = HttpServer::new(|| {
+        App::new()
+            .route("/echo", web::get().to(echo))
+            .route("/search", web::get().to(search))
+    })
+    .workers(1)
+    .listen(listener)
+    .unwrap()
+    .run();
+    actix_web::rt::spawn(server);
+
+    base_url
+}
+
+#[actix_web::test]
+async fn build_url_preserves_the_query() {
+    let base_url = echo_server();
+    let client = Client::new();
+
+    for query in queries() {
+        let url = build_url(&format!("{base_url}/echo"), &[("q", &query), ("page", "2")]).unwrap();
+
+        let body = client.get(url).send().await.unwrap().bytes().await.unwrap();
+        let echoed: BTreeMap<String, String> = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(echoed.len(), 2, "the query {query:?} leaked parameters");
+        assert_eq!(echoed["q"], query);
+        assert_eq!(echoed["page"], "2");
+    }
+}
+
+#[test]
+fn build_url_rejects_an_invalid_base_url() {
+    assert!(build_url("not a url", &[("q", "rust")]).is_err());
+}
+
+#[actix_web::test]
+async fn declarative_engines_preserve_the_query() {
+    let base_url = echo_server();
+    let config = JsonEngineConfig {
+        engine: DeclarativeEngineConfig {
+            display_name: None,
+            categories: vec!["general".to_owned()],
+            url: format!("{base_url}/search?q={{query}}&page={{page}}"),
+            results_per_page: 10,
+            headers: BTreeMap::new(),
+            cookies: BTreeMap::new(),
+        },
+        results: "/results".to_owned(),
+        result_title: "/title".to_owned(),
+        result_url: "/url".to_owned(),
+        result_description: None,
+    };
+    let engine = JsonEngine::new("echo", &config).unwrap();
+
+    for query in queries() {
+        let results = engine
+            .results(&query, 0, random_browser_profile(), &Client::new(), 0)
+            .await
+            .unwrap();
+
+        // The title is trimmed and escaped by the engine.
+        assert_eq!(results[0].1.title, escape_text(query.trim()));
+    }
+}
+
+#[actix_web::test]
+async fn builtin_engines_encode_the_query() {
+    let (base_url, recorded) = recording_server();
+    let mut config = Config {
+        engine_base_urls: ["bing", "brave", "duckduckgo", "mojeek", "startpage"]
+            .map(|engine| (engine.to_owned(), format!("{base_url}/{engine}")))
+            .into(),
+        ..Config::default()
+    };
+    config.searx.pool.instances = vec![format!("{base_url}/searx")];
+    config.librex.instances = vec![format!("{base_url}/librex")];
+    let registry = EngineRegistry::new(&config);
+
+    let query = "a&b=c#d+e f café 日本";
+    for handler in registry.engines() {
+        let (_, engine) = handler.clone().into_name_engine();
+        // the empty pages of the recording server are not parsed into results.
+        let _ = engine
+            .results(query, 0, random_browser_profile(), &Client::new(), 0)
+            .await;
+    }
+
+    let q = "q=a%26b%3Dc%23d%2Be+f+caf%C3%A9+%E6%97%A5%E6%9C%AC";
+    let expected = BTreeMap::from([
+        ("/bing/search", format!("{q}&adlt=off")),
+        ("/brave/search", format!("{q}&offset=0")),
+        (
+            "/duckduckgo/html/",
+            format!("{q}&v=1&o=json&api=%2Fd.js&kp=-2"),
+        ),
+        ("/librex/search.php", format!("{q}&p=0&t=10")),
+        (
+            "/mojeek/search",
+            format!(
+                "{q}&t=10&theme=dark&arc=none&date=1&cdate=1&tlen=100&ref=1&hp=minimal&lb=en\
+                 &qss=Bing%2CBrave%2CDuckDuckGo%2CEcosia%2CGoogle%2CLilo%2CMetager%2CQwant\
+                 %2CStartpage%2CSwisscows%2CYandex%2CYep%2CYou&safe=0"
+            ),
+        ),
+        ("/searx/search", format!("{q}&pageno=1")),
+        ("/startpage/do/dsearch", format!("{q}&num=10&start=0")),
+    ]);
+    let recorded = recorded.lock().unwrap();
+    let received: BTreeMap<&str, String> = recorded
+        .iter()
+        .map(|(path, query_string)| (path.as_str(), query_string.clone()))
+        .collect();
+    assert_eq!(received, expected);
+}